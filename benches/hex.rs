@@ -0,0 +1,97 @@
+// Compares the table driven hex codec in conversions with the original char by char version,
+// which is kept here for reference. Run with `cargo bench`.
+extern crate cryptopals;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use cryptopals::conversions::{hex_to_string, hex_to_string_into, string_to_hex,
+                              try_string_to_hex_into, HexMode};
+
+fn hex_to_char(short: u8) -> char {
+    match short {
+        0x0..=0x9 => (short + b'0') as char,
+        0xa..=0xf => (short - 0xa + b'a') as char,
+        _ => panic!("hex_to_char only converts short values between 0x0 and 0xf"),
+    }
+}
+
+fn char_to_hex(c: char) -> u8 {
+    match c {
+        '0'..='9' => c as u8 - b'0',
+        'a'..='f' => 10 + (c as u8 - b'a'),
+        _ => panic!("char_to_hex only converts char values between '0' and 'f'"),
+    }
+}
+
+fn old_hex_to_string(hex: &[u8]) -> String {
+    let byte_strings: Vec<String> = hex.iter()
+                                       .map(|x| {
+                                           let h = (x & 0xF0) >> 4;
+                                           let l = x & 0x0F;
+                                           format!("{}{}", hex_to_char(h), hex_to_char(l))
+                                       })
+                                       .collect();
+    byte_strings.join("")
+}
+
+fn old_string_to_hex(string: &str) -> Vec<u8> {
+    let mut v = Vec::new();
+    let mut cs = string.chars();
+    loop {
+        let pair = (cs.next(), cs.next());
+        match pair {
+            (Some(h), Some(l)) => v.push((char_to_hex(h) << 4) | char_to_hex(l)),
+            (Some(_), None) => panic!("odd length"),
+            _ => break,
+        }
+    }
+    v
+}
+
+// Runs f enough times to take a while and reports the throughput over size bytes of input.
+fn bench<F: FnMut() -> usize>(name: &str, size: usize, mut f: F) -> Duration {
+    let mut runs = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {
+        black_box(f());
+        runs += 1;
+    }
+    let per_run = start.elapsed() / runs;
+    let mb_per_sec = size as f64 / per_run.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{:<40} {:>12.3?} per run {:>8.1} MiB/s", name, per_run, mb_per_sec);
+    per_run
+}
+
+fn main() {
+    const SIZE: usize = 1 << 20;
+    let bytes: Vec<u8> = (0..SIZE).map(|i| (i * 31 + i / 7) as u8).collect();
+    let text = hex_to_string(&bytes);
+    assert_eq!(old_hex_to_string(&bytes), text);
+    assert_eq!(old_string_to_hex(&text), bytes);
+
+    println!("hex codec over {} bytes", SIZE);
+    let old = bench("encode: old hex_to_string", SIZE, || old_hex_to_string(&bytes).len());
+    let new = bench("encode: hex_to_string", SIZE, || hex_to_string(&bytes).len());
+    let mut s = String::new();
+    bench("encode: hex_to_string_into (reused)", SIZE, || {
+        s.clear();
+        hex_to_string_into(&bytes, &mut s);
+        s.len()
+    });
+    println!("encode speedup: {:.1}x", old.as_secs_f64() / new.as_secs_f64());
+
+    let old = bench("decode: old string_to_hex", SIZE, || old_string_to_hex(&text).len());
+    let new = bench("decode: string_to_hex", SIZE, || string_to_hex(&text).len());
+    let mut v = Vec::new();
+    bench("decode: try_string_to_hex_into (reused)", SIZE, || {
+        v.clear();
+        try_string_to_hex_into(&text, HexMode::strict(), &mut v).unwrap();
+        v.len()
+    });
+    bench("decode: lenient mode", SIZE, || {
+        v.clear();
+        try_string_to_hex_into(&text, HexMode::lenient(), &mut v).unwrap();
+        v.len()
+    });
+    println!("decode speedup: {:.1}x", old.as_secs_f64() / new.as_secs_f64());
+}
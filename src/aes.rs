@@ -0,0 +1,385 @@
+// AES (FIPS-197) written out in full, so block crypto needs no system OpenSSL and every round can
+// be watched or tampered with for fault and side channel experiments. It is a plain table
+// implementation: the S-box lookups are indexed by secret data, so it is not constant time and is
+// for study, not for protecting anything.
+
+use std::error::Error;
+use std::fmt;
+
+// AES always works on 16 byte blocks, whatever the key size.
+pub const BLOCK_SIZE: usize = 16;
+
+// A block, and the cipher's state between rounds. The bytes are in FIPS-197 order, filling the
+// state a column at a time: byte 4 * column + row.
+pub type Block = [u8; BLOCK_SIZE];
+
+pub const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+pub const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+// The round constants used by the key schedule, enough for AES-128's ten.
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+// Returned for keys that aren't 16, 24 or 32 bytes long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidKeyLength {
+    pub len: usize,
+}
+
+impl fmt::Display for InvalidKeyLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AES keys are 16, 24 or 32 bytes, not {}", self.len)
+    }
+}
+
+impl Error for InvalidKeyLength {}
+
+// An expanded key, ready to encrypt and decrypt blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aes {
+    round_keys: Vec<Block>,
+}
+
+impl Aes {
+    // Expands a 16, 24 or 32 byte key for AES-128, AES-192 or AES-256.
+    pub fn new(key: &[u8]) -> Result<Aes, InvalidKeyLength> {
+        match key.len() {
+            16 | 24 | 32 => Ok(Aes { round_keys: expand_key(key) }),
+            len => Err(InvalidKeyLength { len }),
+        }
+    }
+
+    // 10, 12 or 14 for AES-128, AES-192 and AES-256.
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    // The key schedule: the key added before the first round, then one for each round.
+    pub fn round_keys(&self) -> &[Block] {
+        &self.round_keys
+    }
+
+    pub fn encrypt_block(&self, block: &mut Block) {
+        self.encrypt_block_traced(block, |_, _| {});
+    }
+
+    pub fn decrypt_block(&self, block: &mut Block) {
+        self.decrypt_block_traced(block, |_, _| {});
+    }
+
+    // Encrypts block, calling trace with the round number and the state at the end of each
+    // round, 0 being the initial round key addition. Changes trace makes to the state carry on
+    // through the remaining rounds, so faults can be injected anywhere.
+    pub fn encrypt_block_traced<F>(&self, block: &mut Block, mut trace: F)
+        where F: FnMut(usize, &mut Block)
+    {
+        let rounds = self.rounds();
+        add_round_key(block, &self.round_keys[0]);
+        trace(0, block);
+        for round in 1..rounds + 1 {
+            sub_bytes(block);
+            shift_rows(block);
+            if round != rounds {
+                mix_columns(block);
+            }
+            add_round_key(block, &self.round_keys[round]);
+            trace(round, block);
+        }
+    }
+
+    // Decrypts block, the inverse of encrypt_block_traced. Rounds are numbered as for encryption,
+    // counting down, so trace sees the state encryption had at the end of each round, from the
+    // last round to round 0, and round 0's state is passed before the final round key is removed.
+    pub fn decrypt_block_traced<F>(&self, block: &mut Block, mut trace: F)
+        where F: FnMut(usize, &mut Block)
+    {
+        let rounds = self.rounds();
+        for round in (1..rounds + 1).rev() {
+            trace(round, block);
+            add_round_key(block, &self.round_keys[round]);
+            if round != rounds {
+                inv_mix_columns(block);
+            }
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+        }
+        trace(0, block);
+        add_round_key(block, &self.round_keys[0]);
+    }
+
+    // Encrypts data in ECB mode, each block on its own, as in challenge 7. There is no padding,
+    // so panics unless data is a whole number of blocks.
+    pub fn encrypt_ecb(&self, data: &[u8]) -> Vec<u8> {
+        self.ecb(data, Aes::encrypt_block)
+    }
+
+    // Decrypts ECB mode data, without removing any padding. Panics unless data is a whole number
+    // of blocks.
+    pub fn decrypt_ecb(&self, data: &[u8]) -> Vec<u8> {
+        self.ecb(data, Aes::decrypt_block)
+    }
+
+    fn ecb(&self, data: &[u8], cipher: fn(&Aes, &mut Block)) -> Vec<u8> {
+        assert!(data.len().is_multiple_of(BLOCK_SIZE),
+                "ECB data must be a whole number of {} byte blocks",
+                BLOCK_SIZE);
+        let mut out = data.to_vec();
+        for chunk in out.chunks_mut(BLOCK_SIZE) {
+            let mut block = [0; BLOCK_SIZE];
+            block.copy_from_slice(chunk);
+            cipher(self, &mut block);
+            chunk.copy_from_slice(&block);
+        }
+        out
+    }
+}
+
+// FIPS-197 section 5.2. The schedule is built a four byte word at a time, a round key being four
+// consecutive words.
+fn expand_key(key: &[u8]) -> Vec<Block> {
+    let nk = key.len() / 4;
+    let rounds = nk + 6;
+    let mut words: Vec<[u8; 4]> = key.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]).collect();
+    for i in nk..4 * (rounds + 1) {
+        let mut word = words[i - 1];
+        if i % nk == 0 {
+            word = [SBOX[word[1] as usize] ^ RCON[i / nk - 1],
+                    SBOX[word[2] as usize],
+                    SBOX[word[3] as usize],
+                    SBOX[word[0] as usize]];
+        } else if nk > 6 && i % nk == 4 {
+            for b in word.iter_mut() {
+                *b = SBOX[*b as usize];
+            }
+        }
+        let before = words[i - nk];
+        for (b, p) in word.iter_mut().zip(before.iter()) {
+            *b ^= *p;
+        }
+        words.push(word);
+    }
+    words.chunks(4)
+         .map(|w| {
+             let mut key = [0; BLOCK_SIZE];
+             for (i, word) in w.iter().enumerate() {
+                 key[4 * i..4 * i + 4].copy_from_slice(word);
+             }
+             key
+         })
+         .collect()
+}
+
+// The round steps, public so experiments can run partial or modified rounds of their own.
+
+pub fn add_round_key(state: &mut Block, key: &Block) {
+    for (s, k) in state.iter_mut().zip(key.iter()) {
+        *s ^= *k;
+    }
+}
+
+pub fn sub_bytes(state: &mut Block) {
+    for s in state.iter_mut() {
+        *s = SBOX[*s as usize];
+    }
+}
+
+pub fn inv_sub_bytes(state: &mut Block) {
+    for s in state.iter_mut() {
+        *s = INV_SBOX[*s as usize];
+    }
+}
+
+// Rotates row r left by r places.
+pub fn shift_rows(state: &mut Block) {
+    let old = *state;
+    for (i, s) in state.iter_mut().enumerate() {
+        let (column, row) = (i / 4, i % 4);
+        *s = old[4 * ((column + row) % 4) + row];
+    }
+}
+
+pub fn inv_shift_rows(state: &mut Block) {
+    let old = *state;
+    for (i, &s) in old.iter().enumerate() {
+        let (column, row) = (i / 4, i % 4);
+        state[4 * ((column + row) % 4) + row] = s;
+    }
+}
+
+// Multiplies by x in GF(2^8), modulo the AES polynomial.
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = xtime(a);
+        b >>= 1;
+    }
+    product
+}
+
+pub fn mix_columns(state: &mut Block) {
+    for column in state.chunks_mut(4) {
+        let c = [column[0], column[1], column[2], column[3]];
+        for row in 0..4 {
+            column[row] = xtime(c[row]) ^ xtime(c[(row + 1) % 4]) ^ c[(row + 1) % 4] ^
+                          c[(row + 2) % 4] ^ c[(row + 3) % 4];
+        }
+    }
+}
+
+pub fn inv_mix_columns(state: &mut Block) {
+    for column in state.chunks_mut(4) {
+        let c = [column[0], column[1], column[2], column[3]];
+        for row in 0..4 {
+            column[row] = gf_mul(c[row], 0x0e) ^ gf_mul(c[(row + 1) % 4], 0x0b) ^
+                          gf_mul(c[(row + 2) % 4], 0x0d) ^ gf_mul(c[(row + 3) % 4], 0x09);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use conversions::string_to_hex;
+
+    fn block(hex: &str) -> Block {
+        let mut block = [0; BLOCK_SIZE];
+        block.copy_from_slice(&string_to_hex(hex));
+        block
+    }
+
+    #[test]
+    fn test_fips_197_vectors() {
+        // Appendix C, one example for each key size.
+        let plaintext = block("00112233445566778899aabbccddeeff");
+        let cases = [("000102030405060708090a0b0c0d0e0f", "69c4e0d86a7b0430d8cdb78070b4c55a", 10),
+                     ("000102030405060708090a0b0c0d0e0f1011121314151617",
+                      "dda97ca4864cdfe06eaf70a0ec0d7191",
+                      12),
+                     ("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                      "8ea2b7ca516745bfeafc49904b496089",
+                      14)];
+        for &(key, ciphertext, rounds) in cases.iter() {
+            let aes = Aes::new(&string_to_hex(key)).unwrap();
+            assert_eq!(aes.rounds(), rounds);
+            let mut b = plaintext;
+            aes.encrypt_block(&mut b);
+            assert_eq!(b, block(ciphertext));
+            aes.decrypt_block(&mut b);
+            assert_eq!(b, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_key_expansion() {
+        // Appendix A.1 and A.3: the last round keys.
+        let aes = Aes::new(&string_to_hex("2b7e151628aed2a6abf7158809cf4f3c")).unwrap();
+        assert_eq!(aes.round_keys()[10], block("d014f9a8c9ee2589e13f0cc8b6630ca6"));
+        let key = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+        let aes = Aes::new(&string_to_hex(key)).unwrap();
+        assert_eq!(aes.round_keys()[14], block("fe4890d1e6188d0b046df344706c631e"));
+        assert_eq!(Aes::new(b"too short"), Err(InvalidKeyLength { len: 9 }));
+        assert!(Aes::new(&[0; 33]).is_err());
+    }
+
+    #[test]
+    fn test_traced_rounds() {
+        // Appendix B: the state at the start of round 2, and the output.
+        let aes = Aes::new(&string_to_hex("2b7e151628aed2a6abf7158809cf4f3c")).unwrap();
+        let mut b = block("3243f6a8885a308d313198a2e0370734");
+        let mut states = vec![];
+        aes.encrypt_block_traced(&mut b, |round, state| states.push((round, *state)));
+        assert_eq!(states.len(), 11);
+        assert_eq!(states[1], (1, block("a49c7ff2689f352b6b5bea43026a5049")));
+        assert_eq!(b, block("3925841d02dc09fbdc118597196a0b32"));
+
+        // Decryption passes through the same states in reverse.
+        let mut seen = vec![];
+        aes.decrypt_block_traced(&mut b, |round, state| seen.push((round, *state)));
+        seen.reverse();
+        assert_eq!(seen, states);
+
+        // A fault injected before the last round spreads to only four bytes of the output.
+        let mut faulty = block("3243f6a8885a308d313198a2e0370734");
+        aes.encrypt_block_traced(&mut faulty, |round, state| {
+            if round == 8 {
+                state[0] ^= 1;
+            }
+        });
+        let changed = faulty.iter().zip(block("3925841d02dc09fbdc118597196a0b32").iter())
+                            .filter(|&(a, b)| a != b)
+                            .count();
+        assert_eq!(changed, 4);
+    }
+
+    #[test]
+    fn test_round_steps_invert() {
+        let original = block("00112233445566778899aabbccddeeff");
+        let mut state = original;
+        shift_rows(&mut state);
+        assert_eq!(state, block("0055aaff4499ee3388dd2277cc1166bb"));
+        inv_shift_rows(&mut state);
+        mix_columns(&mut state);
+        inv_mix_columns(&mut state);
+        sub_bytes(&mut state);
+        inv_sub_bytes(&mut state);
+        assert_eq!(state, original);
+        assert!((0..256).all(|b| INV_SBOX[SBOX[b] as usize] as usize == b));
+    }
+
+    #[test]
+    fn test_ecb() {
+        let aes = Aes::new(b"YELLOW SUBMARINE").unwrap();
+        let plaintext = b"sixteen byte blksixteen byte blk";
+        let ciphertext = aes.encrypt_ecb(plaintext);
+        assert_eq!(&ciphertext[..16], &ciphertext[16..]);
+        assert_eq!(aes.decrypt_ecb(&ciphertext), plaintext.to_vec());
+        assert_eq!(aes.encrypt_ecb(b""), vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "whole number")]
+    fn test_ecb_partial_block() {
+        Aes::new(b"YELLOW SUBMARINE").unwrap().encrypt_ecb(b"not a block");
+    }
+}
@@ -2,9 +2,10 @@
 
 #[test]
 fn test_fixed_xor() {
-    use combine::xor_each;
+    use combine::fixed_xor;
     use conversions::{string_to_hex, hex_to_string};
-    let x = xor_each(&string_to_hex("1c0111001f010100061a024b53535009181c"),
-                     &string_to_hex("686974207468652062756c6c277320657965"));
+    let x = fixed_xor(&string_to_hex("1c0111001f010100061a024b53535009181c"),
+                      &string_to_hex("686974207468652062756c6c277320657965"))
+                .unwrap();
     assert_eq!(hex_to_string(&x), "746865206b696420646f6e277420706c6179");
 }
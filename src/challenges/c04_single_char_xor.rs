@@ -1,31 +1,18 @@
 #[test]
 fn test_detect_single_character_xor() {
     use conversions::string_to_hex;
-    use combine::xor_byte;
-    use text::CharFreq;
+    use crack::detect_single_byte_xor;
 
     let content = include_str!("data/4.txt");
 
-    let en = CharFreq::for_english();
-    let mut found = None;
-    for line in content.lines() {
-        let mut best = 1000.0;
-        for b in 0x00..0xff {
-            let bytes = string_to_hex(line);
-            let s = String::from_utf8(xor_byte(&bytes, b)).unwrap_or("".to_string());
-            if s.len() > 0 {
-                let mut c = CharFreq::new();
-                c.count_all(&s);
-                let d = c.dist(&en);
-                if d < 1.0 && d < best {
-                    found = Some(s);
-                    best = d;
-                }
-            }
-        }
-        if found.is_some() {
-            break
-        };
-    }
+    let lines: Vec<Vec<u8>> = content.lines().map(string_to_hex).collect();
+    let detections = detect_single_byte_xor(&lines);
+    // The figures quoted by OUTLIER_Z.
+    let significance: Vec<f32> =
+        detections.iter().take(2).map(|d| (d.significance * 10.0).round() / 10.0).collect();
+    assert_eq!(significance, vec![5.2, 2.7]);
+    let found = detections.into_iter()
+                          .find(|d| d.is_likely())
+                          .map(|d| String::from_utf8(d.plaintext).unwrap());
     assert_eq!(found, Some("Now that the party is jumping\n".to_string()));
 }
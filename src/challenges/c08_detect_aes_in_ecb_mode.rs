@@ -1,41 +1,27 @@
 #[test]
 fn test_can_find_aes_ecb_cipher() {
     use conversions::string_to_hex;
-    use std::collections::HashMap;
+    use detect::{infer_block_size, rank_ecb};
 
     let ciphers: Vec<Vec<u8>> = include_str!("data/8.txt")
                                     .lines()
-                                    .map(|x| string_to_hex(x))
+                                    .map(string_to_hex)
                                     .collect();
 
-    let mut dupeidx = vec!();
-    for cipher in ciphers.iter() {
-        let mut block_counts: HashMap<Vec<u8>, usize> = HashMap::new();
-        for block in cipher.chunks(16) {
-            let count = block_counts.entry(block.to_owned()).or_insert(0);
-            *count += 1;
-        }
-        let dupes = block_counts.iter().fold(0, |a, (_, count)| a + count - 1);
-        dupeidx.push(dupes);
-    }
-    let mut found_idx = (0, 0); // idx, dupes
-    for (idx, dupes) in dupeidx.iter().enumerate() {
-        if *dupes > found_idx.1 {
-            found_idx = (idx, *dupes)
-        }
-    }
-    assert!(found_idx.1 > 0);
-    assert_eq!(found_idx.0, 132);
-    println!("Index of aes in ecb mode cipher is {}", found_idx.0);
+    let found = rank_ecb(&ciphers, 16)[0];
+    assert!(found.repeats > 0);
+    assert_eq!(found.index, 132);
+    assert_eq!(infer_block_size(&ciphers[found.index]), Some(16));
+    println!("Index of aes in ecb mode cipher is {}", found.index);
 
     // Thought maybe an easter egg was the text was encrypted with same key as last time.
     // Does not seem to be the case. Maybe we can come back and try to crack this thing later.
     /*
-    use openssl::crypto::symm::{decrypt, Type};
+    use aes::Aes;
     const KEY: &'static str = "YELLOW SUBMARINE";
-    let message = String::from_utf8(decrypt(Type::AES_128_ECB, KEY.as_bytes(),
-                                            &[],
-                                            ciphers.get(found_idx.0).unwrap())).unwrap();
+    let message = String::from_utf8(Aes::new(KEY.as_bytes())
+                                        .unwrap()
+                                        .decrypt_ecb(&ciphers[found.index])).unwrap();
     println!("Message is {}?", message);
     */
 }
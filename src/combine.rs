@@ -1,10 +1,14 @@
+use std::borrow::Borrow;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
 pub fn xor_each(source: &[u8], with: &[u8]) -> Vec<u8> {
-    let mut v = Vec::new();
-    let xiter = with.iter().cycle();
-    let pairs = source.iter().zip(xiter);
-    for (a, b) in pairs {
-        v.push(a ^ b);
+    if with.is_empty() {
+        return Vec::new();
     }
+    let mut v = source.to_vec();
+    xor_in_place(&mut v, with);
     v
 }
 
@@ -12,6 +16,138 @@ pub fn xor_byte(source: &[u8], with: u8) -> Vec<u8> {
     xor_each(source, &[with])
 }
 
+// Xors two slices of the same length together, a word at a time where possible.
+fn xor_words(data: &mut [u8], with: &[u8]) {
+    debug_assert_eq!(data.len(), with.len());
+    let mut words = data.chunks_exact_mut(8);
+    let mut with_words = with.chunks_exact(8);
+    for (a, b) in (&mut words).zip(&mut with_words) {
+        let x = u64::from_ne_bytes((&*a).try_into().unwrap()) ^
+                u64::from_ne_bytes(b.try_into().unwrap());
+        a.copy_from_slice(&x.to_ne_bytes());
+    }
+    for (a, b) in words.into_remainder().iter_mut().zip(with_words.remainder()) {
+        *a ^= *b;
+    }
+}
+
+// Xors data with key in place, repeating the key as needed like xor_each. Panics if the key is
+// empty.
+pub fn xor_in_place(data: &mut [u8], key: &[u8]) {
+    assert!(!key.is_empty(), "can't xor with an empty key");
+    if key.len() >= data.len() {
+        let n = data.len();
+        xor_words(data, &key[..n]);
+        return;
+    }
+    // Repeat the key out to a whole number of words so each block can be xored a word at a time.
+    let block: Vec<u8> = key.iter().cycle().take(key.len() * 8).cloned().collect();
+    for chunk in data.chunks_mut(block.len()) {
+        let n = chunk.len();
+        xor_words(chunk, &block[..n]);
+    }
+}
+
+// Repeating key xor over data that arrives a piece at a time, such as a stream read in chunks.
+// Keeps track of where in the key the next piece starts, so the result is the same as calling
+// xor_each on all of the data at once.
+#[derive(Debug, Clone)]
+pub struct RepeatingXor {
+    // The key twice over, so the key starting at any position is a single slice.
+    doubled: Vec<u8>,
+    pos: usize,
+}
+
+impl RepeatingXor {
+    // Panics if the key is empty.
+    pub fn new(key: &[u8]) -> RepeatingXor {
+        assert!(!key.is_empty(), "can't xor with an empty key");
+        let mut doubled = key.to_vec();
+        doubled.extend_from_slice(key);
+        RepeatingXor { doubled, pos: 0 }
+    }
+
+    // Xors the next piece of data in place.
+    pub fn apply(&mut self, data: &mut [u8]) {
+        let key_len = self.doubled.len() / 2;
+        xor_in_place(data, &self.doubled[self.pos..self.pos + key_len]);
+        self.pos = (self.pos + data.len()) % key_len;
+    }
+}
+
+// The error from fixed_xor when its inputs are different lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "can't xor buffers of different lengths ({} and {} bytes)",
+               self.left,
+               self.right)
+    }
+}
+
+impl Error for LengthMismatch {}
+
+// Xors two buffers of equal length, as in challenge 2. Unlike xor_each, neither side is repeated.
+pub fn fixed_xor(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+    if a.len() != b.len() {
+        return Err(LengthMismatch { left: a.len(), right: b.len() });
+    }
+    let mut v = a.to_vec();
+    xor_words(&mut v, b);
+    Ok(v)
+}
+
+// Iterator returned by xor_stream.
+#[derive(Debug, Clone)]
+pub struct XorStream<I, K> {
+    source: I,
+    keystream: K,
+}
+
+impl<I, K> Iterator for XorStream<I, K>
+    where I: Iterator,
+          I::Item: Borrow<u8>,
+          K: Iterator,
+          K::Item: Borrow<u8>
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        match (self.source.next(), self.keystream.next()) {
+            (Some(a), Some(b)) => Some(*a.borrow() ^ *b.borrow()),
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (slo, shi) = self.source.size_hint();
+        let (klo, khi) = self.keystream.size_hint();
+        let hi = match (shi, khi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        (slo.min(klo), hi)
+    }
+}
+
+// Xors a stream of bytes with a keystream, ending when either runs out. Both sides can yield
+// bytes or references to bytes, so `xor_stream(data.iter(), key.iter().cycle())` is the lazy
+// equivalent of xor_each.
+pub fn xor_stream<I, K>(source: I, keystream: K) -> XorStream<I::IntoIter, K::IntoIter>
+    where I: IntoIterator,
+          I::Item: Borrow<u8>,
+          K: IntoIterator,
+          K::Item: Borrow<u8>
+{
+    XorStream { source: source.into_iter(), keystream: keystream.into_iter() }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -22,4 +158,68 @@ mod test {
         let x = xor_byte(&string_to_hex("4f7221752667267274677627"), 6);
         assert_eq!(String::from_utf8(x).unwrap(), "It's a trap!");
     }
+
+    #[test]
+    fn test_xor_in_place() {
+        let data: Vec<u8> = (0..100u32).map(|x| (x * 37) as u8).collect();
+        for key_len in 1..20 {
+            let key: Vec<u8> = (0..key_len).map(|x| (x * 91 + 7) as u8).collect();
+            for len in 0..data.len() {
+                let expected: Vec<u8> = data[..len]
+                                            .iter()
+                                            .zip(key.iter().cycle())
+                                            .map(|(a, b)| a ^ b)
+                                            .collect();
+                let mut buf = data[..len].to_vec();
+                xor_in_place(&mut buf, &key);
+                assert_eq!(buf, expected);
+                assert_eq!(xor_each(&data[..len], &key), expected);
+            }
+        }
+        assert_eq!(xor_each(&data, &[]), vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty key")]
+    fn test_xor_in_place_empty_key() {
+        xor_in_place(&mut [1, 2, 3], &[]);
+    }
+
+    #[test]
+    fn test_repeating_xor() {
+        let data = b"Burning 'em, if you ain't quick and nimble\nI go crazy when I hear a cymbal";
+        let expected = xor_each(data, b"ICE");
+        for step in 1..8 {
+            let mut x = RepeatingXor::new(b"ICE");
+            let mut buf = data.to_vec();
+            for chunk in buf.chunks_mut(step) {
+                x.apply(chunk);
+            }
+            assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn test_fixed_xor() {
+        assert_eq!(fixed_xor(&[0xf0, 0x0f, 0xaa], &[0xff, 0xff, 0x0f]),
+                   Ok(vec![0x0f, 0xf0, 0xa5]));
+        assert_eq!(fixed_xor(&[], &[]), Ok(vec![]));
+        assert_eq!(fixed_xor(&[1, 2, 3], &[1, 2]),
+                   Err(LengthMismatch { left: 3, right: 2 }));
+    }
+
+    #[test]
+    fn test_xor_stream() {
+        let data = b"It's a trap!";
+        let key = b"ICE";
+        let x: Vec<u8> = xor_stream(data.iter(), key.iter().cycle()).collect();
+        assert_eq!(x, xor_each(data, key));
+
+        // A counter as the keystream, stopping when the shorter side ends.
+        let x: Vec<u8> = xor_stream(vec![0xffu8; 4], 0u8..).collect();
+        assert_eq!(x, vec![0xff, 0xfe, 0xfd, 0xfc]);
+        let s = xor_stream(vec![0u8; 10], 0u8..3);
+        assert_eq!(s.size_hint(), (3, Some(3)));
+        assert_eq!(s.count(), 3);
+    }
 }
@@ -0,0 +1,271 @@
+// Reading and writing values of arbitrary bit width to and from byte buffers. Codecs like base64
+// and base32 are just a matter of reading the input a few bits at a time and writing the decoded
+// values back out the same way.
+
+use std::cmp;
+
+// Which end of each byte bits are taken from first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    // The high bit of each byte comes first, and values are read and written high bit first.
+    // This is the order used by base64, base32 and most network formats.
+    MsbFirst,
+    // The low bit of each byte comes first, and values are read and written low bit first, as in
+    // DEFLATE and many compression formats.
+    LsbFirst,
+}
+
+fn mask(bits: u32) -> u64 {
+    if bits == 64 {
+        !0
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn check_stride(bits: u32) {
+    assert!((1..=64).contains(&bits), "bit strides must be between 1 and 64");
+}
+
+// Reads values of 1 to 64 bits at a time from a slice of bytes.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    order: BitOrder,
+    // Index of the current byte, and how many of its bits have been read.
+    idx: usize,
+    bidx: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8], order: BitOrder) -> BitReader<'a> {
+        BitReader { bytes, order, idx: 0, bidx: 0 }
+    }
+
+    // Number of bits left to read.
+    pub fn remaining(&self) -> usize {
+        (self.bytes.len() - self.idx) * 8 - self.bidx as usize
+    }
+
+    // Reads the next value of the given width, or None if there aren't that many bits left, in
+    // which case nothing is consumed.
+    pub fn read(&mut self, bits: u32) -> Option<u64> {
+        check_stride(bits);
+        if self.remaining() < bits as usize {
+            return None;
+        }
+        let mut need = bits;
+        let mut value = 0;
+        while need > 0 {
+            let byte = self.bytes[self.idx] as u64;
+            let avail = 8 - self.bidx;
+            let take = cmp::min(avail, need);
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let chunk = (byte >> (avail - take)) & mask(take);
+                    value = (value << take) | chunk;
+                }
+                BitOrder::LsbFirst => {
+                    let chunk = (byte >> self.bidx) & mask(take);
+                    value |= chunk << (bits - need);
+                }
+            }
+            need -= take;
+            self.bidx += take;
+            if self.bidx == 8 {
+                self.bidx = 0;
+                self.idx += 1;
+            }
+        }
+        Some(value)
+    }
+
+    // Like read, but if fewer bits than asked for remain it returns those that do, padded with
+    // zero bits as if the input carried on. Returns None only once every bit has been read.
+    pub fn read_padded(&mut self, bits: u32) -> Option<u64> {
+        check_stride(bits);
+        let remaining = self.remaining();
+        if remaining == 0 {
+            return None;
+        }
+        if remaining >= bits as usize {
+            return self.read(bits);
+        }
+        let short = remaining as u32;
+        let value = self.read(short).unwrap();
+        match self.order {
+            BitOrder::MsbFirst => Some(value << (bits - short)),
+            BitOrder::LsbFirst => Some(value),
+        }
+    }
+}
+
+// Packs values of 1 to 64 bits at a time into bytes.
+#[derive(Debug, Clone)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    order: BitOrder,
+    // The byte being filled, and how many of its bits have been written.
+    partial: u8,
+    pbits: u32,
+}
+
+impl BitWriter {
+    pub fn new(order: BitOrder) -> BitWriter {
+        BitWriter::from_vec(Vec::new(), order)
+    }
+
+    // Creates a writer that appends to the bytes already in v.
+    pub fn from_vec(v: Vec<u8>, order: BitOrder) -> BitWriter {
+        BitWriter { bytes: v, order, partial: 0, pbits: 0 }
+    }
+
+    // Writes the low bits of value. Any higher bits are ignored.
+    pub fn write(&mut self, value: u64, bits: u32) {
+        check_stride(bits);
+        let value = value & mask(bits);
+        let mut left = bits;
+        while left > 0 {
+            let space = 8 - self.pbits;
+            let take = cmp::min(space, left);
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let chunk = (value >> (left - take)) & mask(take);
+                    self.partial |= (chunk as u8) << (space - take);
+                }
+                BitOrder::LsbFirst => {
+                    let chunk = (value >> (bits - left)) & mask(take);
+                    self.partial |= (chunk as u8) << self.pbits;
+                }
+            }
+            left -= take;
+            self.pbits += take;
+            if self.pbits == 8 {
+                self.bytes.push(self.partial);
+                self.partial = 0;
+                self.pbits = 0;
+            }
+        }
+    }
+
+    // The bytes completed so far.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    // Number of bits written to the current, incomplete byte.
+    pub fn pending_bits(&self) -> u32 {
+        self.pbits
+    }
+
+    // Removes and returns the bytes completed so far, leaving any incomplete byte in place.
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        let cap = self.bytes.capacity();
+        ::std::mem::replace(&mut self.bytes, Vec::with_capacity(cap))
+    }
+
+    // Returns everything written, with any incomplete byte filled out with zero bits.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        if self.pbits > 0 {
+            self.bytes.push(self.partial);
+        }
+        self.bytes
+    }
+
+    // Returns the complete bytes written, discarding the bits of any incomplete byte.
+    pub fn into_whole_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_msb_first() {
+        let mut w = BitWriter::new(BitOrder::MsbFirst);
+        w.write(0b101, 3);
+        w.write(0b11111, 5);
+        w.write(0xabc, 12);
+        assert_eq!(w.pending_bits(), 4);
+        assert_eq!(w.bytes(), &[0b10111111, 0xab]);
+        assert_eq!(w.clone().into_whole_bytes(), vec![0b10111111, 0xab]);
+        assert_eq!(w.into_bytes(), vec![0b10111111, 0xab, 0xc0]);
+
+        let bytes = [0b10111111, 0xab, 0xc0];
+        let mut r = BitReader::new(&bytes, BitOrder::MsbFirst);
+        assert_eq!(r.read(3), Some(0b101));
+        assert_eq!(r.read(5), Some(0b11111));
+        assert_eq!(r.read(12), Some(0xabc));
+        assert_eq!(r.remaining(), 4);
+        assert_eq!(r.read(5), None);
+        assert_eq!(r.read_padded(5), Some(0));
+        assert_eq!(r.read_padded(5), None);
+
+        let mut r = BitReader::new(&[0xff], BitOrder::MsbFirst);
+        assert_eq!(r.read(6), Some(0b111111));
+        assert_eq!(r.read_padded(6), Some(0b110000));
+    }
+
+    #[test]
+    fn test_lsb_first() {
+        let mut w = BitWriter::new(BitOrder::LsbFirst);
+        w.write(0b101, 3);
+        w.write(0b11111, 5);
+        w.write(0xabc, 12);
+        assert_eq!(w.into_bytes(), vec![0b11111101, 0xbc, 0x0a]);
+
+        let bytes = [0b11111101, 0xbc, 0x0a];
+        let mut r = BitReader::new(&bytes, BitOrder::LsbFirst);
+        assert_eq!(r.read(3), Some(0b101));
+        assert_eq!(r.read(5), Some(0b11111));
+        assert_eq!(r.read(12), Some(0xabc));
+        assert_eq!(r.read_padded(8), Some(0));
+
+        let mut r = BitReader::new(&[0xff], BitOrder::LsbFirst);
+        assert_eq!(r.read(6), Some(0b111111));
+        assert_eq!(r.read_padded(6), Some(0b000011));
+    }
+
+    #[test]
+    fn test_round_trip_all_strides() {
+        for &order in [BitOrder::MsbFirst, BitOrder::LsbFirst].iter() {
+            for bits in 1..65 {
+                let values: Vec<u64> = (0..17u64)
+                                           .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15) & mask(bits))
+                                           .collect();
+                let mut w = BitWriter::from_vec(vec![0xee], order);
+                for &v in values.iter() {
+                    w.write(v, bits);
+                }
+                let bytes = w.into_bytes();
+                assert_eq!(bytes.len(), 1 + (17 * bits as usize).div_ceil(8));
+                assert_eq!(bytes[0], 0xee);
+
+                let mut r = BitReader::new(&bytes[1..], order);
+                for &v in values.iter() {
+                    assert_eq!(r.read(bits), Some(v));
+                }
+                assert!(r.remaining() < 8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_take_bytes() {
+        let mut w = BitWriter::new(BitOrder::MsbFirst);
+        w.write(0x123, 12);
+        assert_eq!(w.take_bytes(), vec![0x12]);
+        w.write(0x4, 4);
+        w.write(0x5, 4);
+        assert_eq!(w.take_bytes(), vec![0x34]);
+        assert_eq!(w.into_bytes(), vec![0x50]);
+    }
+
+    #[test]
+    #[should_panic(expected = "between 1 and 64")]
+    fn test_stride_too_wide() {
+        BitWriter::new(BitOrder::MsbFirst).write(0, 65);
+    }
+}
@@ -0,0 +1,176 @@
+// Works out how an input of unknown provenance is encoded.
+//
+// Each candidate encoding that decodes the input without error is scored by how likely the
+// input's symbols would be if they were drawn uniformly from that encoding's alphabet, so an
+// input that only uses hex digits is far more likely to be hex than base64 even though both
+// decode it. Raw bytes are always a candidate, with every byte value equally likely. Confidences
+// are those likelihoods normalised over the candidates, with equal priors.
+
+use conversions::{try_string_to_hex_with, Base32, Base64, DecodeError, HexMode, Padding,
+                  BASE32_ALPHABET, STANDARD_ALPHABET, URL_SAFE_ALPHABET};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+    Base64Url,
+    Base32,
+    Raw,
+}
+
+impl Encoding {
+    // Number of distinct symbols the encoding uses to carry data.
+    fn alphabet_size(&self) -> f64 {
+        match *self {
+            Encoding::Hex => 16.0,
+            Encoding::Base32 => 32.0,
+            Encoding::Base64 | Encoding::Base64Url => 64.0,
+            Encoding::Raw => 256.0,
+        }
+    }
+}
+
+// One way of reading an input: the encoding, how confident we are in it between 0 and 1, and
+// the bytes it decodes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub encoding: Encoding,
+    pub confidence: f64,
+    pub bytes: Vec<u8>,
+}
+
+// Tries to decode input that has had its whitespace removed as an encoding, accepting either case
+// of hex and base32 or base64 with or without padding. Base32 has to be all one case, as mixed
+// case is much more likely to be base64.
+fn decode_as(compact: &[u8], encoding: Encoding) -> Option<Vec<u8>> {
+    let padded_or_not = |alphabet| Base64::new(alphabet, Padding::Optional);
+    let result: Result<Vec<u8>, DecodeError> = match encoding {
+        Encoding::Hex => {
+            return ::std::str::from_utf8(compact)
+                       .ok()
+                       .and_then(|s| try_string_to_hex_with(s, HexMode::lenient()).ok())
+        }
+        Encoding::Base64 => padded_or_not(STANDARD_ALPHABET).decode(compact),
+        Encoding::Base64Url => padded_or_not(URL_SAFE_ALPHABET).decode(compact),
+        Encoding::Base32 => {
+            let base32 = Base32::new(BASE32_ALPHABET, Padding::Optional);
+            if compact.iter().any(|b| b.is_ascii_uppercase()) {
+                base32.decode(compact)
+            } else {
+                base32.decode(compact.to_ascii_uppercase())
+            }
+        }
+        Encoding::Raw => Ok(compact.to_vec()),
+    };
+    result.ok()
+}
+
+// Returns every encoding that input could be in along with what it decodes to, most likely
+// first. Raw is always included, so the result is never empty.
+pub fn detect_encodings(input: &[u8]) -> Vec<Detection> {
+    let compact: Vec<u8> = input.iter().cloned().filter(|b| !b.is_ascii_whitespace()).collect();
+    if compact.is_empty() {
+        return vec![Detection {
+                        encoding: Encoding::Raw,
+                        confidence: 1.0,
+                        bytes: input.to_vec(),
+                    }];
+    }
+
+    let mut candidates = vec![Encoding::Hex, Encoding::Base32, Encoding::Base64];
+    // The two base64 alphabets only differ in their last two symbols, and decode identically
+    // without them, so only consider base64url when it is the only way to read the input.
+    if compact.iter().any(|&b| b == b'-' || b == b'_') {
+        candidates.push(Encoding::Base64Url);
+    }
+
+    // (encoding, log2 likelihood, bytes)
+    let mut found: Vec<(Encoding, f64, Vec<u8>)> = candidates.into_iter()
+        .filter_map(|e| {
+            decode_as(&compact, e).map(|bytes| {
+                (e, -(compact.len() as f64) * e.alphabet_size().log2(), bytes)
+            })
+        })
+        .collect();
+    found.push((Encoding::Raw,
+                -(input.len() as f64) * Encoding::Raw.alphabet_size().log2(),
+                input.to_vec()));
+
+    // Normalise in log space, as the likelihoods themselves underflow for long inputs.
+    let best = found.iter().fold(f64::MIN, |a, f| a.max(f.1));
+    let total: f64 = found.iter().map(|f| (f.1 - best).exp2()).sum();
+    let mut detections: Vec<Detection> = found.into_iter()
+        .map(|(e, l, bytes)| {
+            Detection { encoding: e, confidence: (l - best).exp2() / total, bytes }
+        })
+        .collect();
+    detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    detections
+}
+
+// Works out the most likely encoding of input and decodes it. Anything that isn't valid in any
+// of the text encodings comes back as Raw, unchanged.
+pub fn detect_and_decode(input: &[u8]) -> Detection {
+    detect_encodings(input).remove(0)
+}
+
+#[test]
+fn test_detect_challenge_data() {
+    use conversions::Base64Mode;
+
+    for line in include_str!("../challenges/data/4.txt").lines() {
+        assert_eq!(detect_and_decode(line.as_bytes()).encoding, Encoding::Hex);
+    }
+    for line in include_str!("../challenges/data/8.txt").lines() {
+        let d = detect_and_decode(line.as_bytes());
+        assert_eq!(d.encoding, Encoding::Hex);
+        assert!(d.confidence > 0.99);
+    }
+    for text in [include_str!("../challenges/data/6.txt"),
+                 include_str!("../challenges/data/7.txt")]
+                    .iter() {
+        let d = detect_and_decode(text.as_bytes());
+        assert_eq!(d.encoding, Encoding::Base64);
+        assert!(d.confidence > 0.99);
+        assert_eq!(d.bytes,
+                   Base64::standard().decode_with(*text, Base64Mode::lenient()).unwrap());
+    }
+}
+
+#[test]
+fn test_detect_and_decode() {
+    use conversions::{hex_to_base32, hex_to_base64, hex_to_string};
+
+    let b: Vec<u8> = (0..=255).rev().collect();
+    let d = detect_and_decode(hex_to_string(&b).as_bytes());
+    assert_eq!((d.encoding, d.bytes), (Encoding::Hex, b.clone()));
+    let d = detect_and_decode(hex_to_base64(&b).as_bytes());
+    assert_eq!((d.encoding, d.bytes), (Encoding::Base64, b.clone()));
+    let d = detect_and_decode(Base64::url_safe_unpadded().encode(&b).as_bytes());
+    assert_eq!((d.encoding, d.bytes), (Encoding::Base64Url, b.clone()));
+    let d = detect_and_decode(hex_to_base32(&b).as_bytes());
+    assert_eq!((d.encoding, d.bytes), (Encoding::Base32, b.clone()));
+    let d = detect_and_decode(&b);
+    assert_eq!((d.encoding, d.confidence, d.bytes), (Encoding::Raw, 1.0, b.clone()));
+
+    // Lowercase base32, as in onion addresses and pasted secrets, is still base32.
+    let d = detect_and_decode(b"mzxw6ytboi======");
+    assert_eq!((d.encoding, d.bytes), (Encoding::Base32, b"foobar".to_vec()));
+    let d = detect_and_decode(hex_to_base32(&b).to_lowercase().as_bytes());
+    assert_eq!((d.encoding, d.bytes), (Encoding::Base32, b.clone()));
+
+    // Uppercase hex and hex dumps with whitespace are still hex.
+    let d = detect_and_decode(b"DE AD BE EF\n");
+    assert_eq!((d.encoding, d.bytes), (Encoding::Hex, vec![0xde, 0xad, 0xbe, 0xef]));
+
+    // Short inputs are ambiguous, and say so.
+    let ds = detect_encodings(b"ab");
+    assert_eq!(ds[0].encoding, Encoding::Hex);
+    assert_eq!(ds[1].encoding, Encoding::Base32);
+    assert_eq!(ds[2].encoding, Encoding::Base64);
+    assert!(ds[0].confidence < 0.95);
+    assert!((ds.iter().map(|d| d.confidence).sum::<f64>() - 1.0).abs() < 1e-9);
+
+    let d = detect_and_decode(b"");
+    assert_eq!((d.encoding, d.bytes), (Encoding::Raw, vec![]));
+}
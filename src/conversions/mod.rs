@@ -0,0 +1,1153 @@
+use std::error::Error;
+use std::fmt;
+use std::iter::Iterator;
+
+pub mod bits;
+pub mod detect;
+pub mod stream;
+
+pub use self::bits::{BitOrder, BitReader, BitWriter};
+pub use self::detect::{detect_and_decode, detect_encodings, Detection, Encoding};
+
+// The characters for each hex digit value, 0x0...0xf.
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+// Marks whitespace in a hex decode table.
+const HEX_SPACE: u8 = 0xfe;
+
+// Builds a table mapping every byte to the hex digit value it represents, HEX_SPACE for the
+// whitespace that lenient decoding skips, or INVALID.
+const fn hex_decode_table(uppercase: bool) -> [u8; 256] {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 256 {
+        let c = i as u8;
+        table[i] = match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => 10 + (c - b'a'),
+            b'A'..=b'F' if uppercase => 10 + (c - b'A'),
+            b' ' | b'\t' | b'\n' | b'\r' | 0x0c => HEX_SPACE,
+            _ => INVALID,
+        };
+        i += 1;
+    }
+    table
+}
+
+static HEX_LOWERCASE: [u8; 256] = hex_decode_table(false);
+static HEX_ANY_CASE: [u8; 256] = hex_decode_table(true);
+
+// Takes a slice of bytes and converts to a hex string composed of '0'...'9','a'...'f' characters.
+pub fn hex_to_string(hex: &[u8]) -> String {
+    let mut s = String::with_capacity(hex.len() * 2);
+    hex_to_string_into(hex, &mut s);
+    s
+}
+
+// Like hex_to_string, but appends to an existing string so its allocation can be reused.
+pub fn hex_to_string_into(hex: &[u8], out: &mut String) {
+    out.reserve(hex.len() * 2);
+    for &x in hex {
+        out.push(HEX_DIGITS[(x >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(x & 0x0f) as usize] as char);
+    }
+}
+
+// Errors that can occur while decoding a hex string into bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    // A character that is not a hex digit (in the chosen mode) at the given byte offset.
+    InvalidChar { c: char, offset: usize },
+    // The input held this many hex digits, which can't be paired up into bytes.
+    OddLength(usize),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HexError::InvalidChar { c, offset } => {
+                write!(f, "invalid hex character {:?} at offset {}", c, offset)
+            }
+            HexError::OddLength(n) => {
+                write!(f, "odd number of hex digits ({}), strings need pairs of characters", n)
+            }
+        }
+    }
+}
+
+impl Error for HexError {}
+
+// Controls which inputs the hex decoder will accept beyond strict lowercase digit pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexMode {
+    // Accept 'A'...'F' as well as 'a'...'f'.
+    pub uppercase: bool,
+    // Skip spaces, tabs and line breaks anywhere in the input.
+    pub whitespace: bool,
+}
+
+impl HexMode {
+    // Only '0'...'9','a'...'f' with nothing in between, as produced by hex_to_string.
+    pub fn strict() -> HexMode {
+        HexMode { uppercase: false, whitespace: false }
+    }
+
+    // Either case, and whitespace is ignored; good for hex dumps pasted from other tools.
+    pub fn lenient() -> HexMode {
+        HexMode { uppercase: true, whitespace: true }
+    }
+}
+
+// Takes a character '0'...'9' or 'a'...'f' (or 'A'...'F' if uppercase is allowed) and returns
+// the value of the hex digit it represents.
+fn hex_digit(c: char, uppercase: bool) -> Option<u8> {
+    match c {
+        '0'..='9' => Some(c as u8 - b'0'),
+        'a'..='f' => Some(10 + (c as u8 - b'a')),
+        'A'..='F' if uppercase => Some(10 + (c as u8 - b'A')),
+        _ => None,
+    }
+}
+
+// Decodes a hex string into bytes according to mode, reporting the first problem found.
+pub fn try_string_to_hex_with(string: &str, mode: HexMode) -> Result<Vec<u8>, HexError> {
+    let mut v = Vec::with_capacity(string.len() / 2);
+    try_string_to_hex_into(string, mode, &mut v)?;
+    Ok(v)
+}
+
+// Like try_string_to_hex_with, but appends to an existing vector so its allocation can be
+// reused. On error nothing is appended.
+pub fn try_string_to_hex_into(string: &str, mode: HexMode, out: &mut Vec<u8>)
+                              -> Result<(), HexError> {
+    let table = if mode.uppercase {
+        &HEX_ANY_CASE
+    } else {
+        &HEX_LOWERCASE
+    };
+    let bytes = string.as_bytes();
+    let start = out.len();
+    out.reserve(bytes.len() / 2);
+
+    // Both loops bail out at the first byte they can't use, leaving hex_error to work out exactly
+    // what went wrong.
+    let mut complete = true;
+    if !mode.whitespace {
+        complete = bytes.len().is_multiple_of(2);
+        for pair in bytes.chunks_exact(2) {
+            let h = table[pair[0] as usize];
+            let l = table[pair[1] as usize];
+            if (h | l) > 0x0f {
+                complete = false;
+                break;
+            }
+            out.push((h << 4) | l);
+        }
+    } else {
+        let mut high = None;
+        for &b in bytes {
+            let nibble = table[b as usize];
+            if nibble == HEX_SPACE {
+                continue;
+            }
+            if nibble > 0x0f {
+                complete = false;
+                break;
+            }
+            match high.take() {
+                Some(h) => out.push((h << 4) | nibble),
+                None => high = Some(nibble),
+            }
+        }
+        complete = complete && high.is_none();
+    }
+    if complete {
+        return Ok(());
+    }
+    out.truncate(start);
+    Err(hex_error(string, mode))
+}
+
+// Finds the first problem with a hex string that failed to decode. This walks characters rather
+// than bytes, so that non-ASCII input is reported as the character it is.
+fn hex_error(string: &str, mode: HexMode) -> HexError {
+    let mut digits = 0;
+    for (offset, c) in string.char_indices() {
+        if mode.whitespace && c.is_ascii_whitespace() {
+            continue;
+        }
+        if hex_digit(c, mode.uppercase).is_none() {
+            return HexError::InvalidChar { c, offset };
+        }
+        digits += 1;
+    }
+    assert!(digits % 2 == 1, "hex_error called on valid hex");
+    HexError::OddLength(digits)
+}
+
+// Strict version of try_string_to_hex_with, accepting only what hex_to_string produces.
+pub fn try_string_to_hex(string: &str) -> Result<Vec<u8>, HexError> {
+    try_string_to_hex_with(string, HexMode::strict())
+}
+
+// Takes a string with only '0'...'9','a'...'f' characters in it and converts to the represented vector of bytes.
+// Panics if the string is not valid hex; see try_string_to_hex for a fallible version.
+pub fn string_to_hex(string: &str) -> Vec<u8> {
+    match try_string_to_hex(string) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+// Wrapper arround hex bytes that allows returning n-bits at a time (stride), most significant bit
+// first unless another order is asked for. The last value is padded out with zero bits if the
+// bytes run out part way through it. stride must be less than or equal to 8.
+struct Bits<'a> {
+    reader: BitReader<'a>,
+    stride: u32,
+}
+
+impl <'a>Bits<'a> {
+    fn new(wrap: &'a [u8], stride: u32) -> Bits<'a> {
+        Bits::with_order(wrap, stride, BitOrder::MsbFirst)
+    }
+
+    fn with_order(wrap: &'a [u8], stride: u32, order: BitOrder) -> Bits<'a> {
+        assert!(stride <= 8);
+        Bits { reader: BitReader::new(wrap, order), stride }
+    }
+}
+
+impl <'a>Iterator for Bits<'a> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        self.reader.read_padded(self.stride).map(|x| x as u8)
+    }
+}
+
+// Errors that can occur while decoding base64, base32 or ascii85. Offsets are byte offsets into
+// the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    // A byte that is neither in the alphabet nor padding.
+    InvalidSymbol { byte: u8, offset: usize },
+    // Padding in the wrong place, or input that ends part way through a group of symbols.
+    InvalidPadding { offset: usize },
+    // Anything other than (skipped) whitespace following the end of the encoded data.
+    TrailingData { offset: usize },
+    // An ascii85 group whose value does not fit in four bytes.
+    Overflow { offset: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidSymbol { byte, offset } => {
+                write!(f, "invalid symbol {:?} at offset {}", byte as char, offset)
+            }
+            DecodeError::InvalidPadding { offset } => {
+                write!(f, "invalid padding at offset {}", offset)
+            }
+            DecodeError::TrailingData { offset } => {
+                write!(f, "unexpected data after the end of input at offset {}", offset)
+            }
+            DecodeError::Overflow { offset } => {
+                write!(f, "group value does not fit in four bytes at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+// Controls which inputs the base64 decoder will accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Mode {
+    // Skip spaces, tabs and line breaks anywhere in the input.
+    pub whitespace: bool,
+}
+
+impl Base64Mode {
+    // A single unbroken run of base64, as produced by hex_to_base64.
+    pub fn strict() -> Base64Mode {
+        Base64Mode { whitespace: false }
+    }
+
+    // Tolerates line wrapping and other whitespace, as found in files like challenges/data/6.txt.
+    pub fn lenient() -> Base64Mode {
+        Base64Mode { whitespace: true }
+    }
+}
+
+// The standard base64 alphabet from RFC 4648 section 4.
+pub const STANDARD_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// The URL and filename safe alphabet from RFC 4648 section 5, as used by JWTs.
+pub const URL_SAFE_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// The alphabet used by crypt(3) style password hashes (DES, MD5 and SHA crypt).
+pub const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// The alphabet used by bcrypt password hashes.
+pub const BCRYPT_ALPHABET: &[u8; 64] =
+    b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+// Marks bytes that are not part of an alphabet in a decode table.
+const INVALID: u8 = 0xff;
+
+// How a base64 or base32 engine treats '=' padding at the end of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    // Always written, and the decoder insists on whole groups of symbols.
+    Required,
+    // Never written, and the decoder rejects '='.
+    Forbidden,
+    // Never written, but the decoder accepts input with or without it.
+    Optional,
+}
+
+// A base64 encoder and decoder for a particular alphabet and padding policy.
+//
+// Each group of three bytes is read six bits at a time, most significant bit first as RFC 4648
+// has it, or least significant first as MD5 and SHA crypt do. Besides the bit order, only the
+// symbols used for the six bit values and the handling of the final partial group differ. Those
+// crypt schemes also take the bytes of the digest in a scheme specific order, so the caller has
+// to put them in that order before encoding, and back after decoding.
+#[derive(Clone, Copy)]
+pub struct Base64 {
+    alphabet: [u8; 64],
+    decode: [u8; 256],
+    padding: Padding,
+    order: BitOrder,
+}
+
+impl Base64 {
+    // Creates an engine from an alphabet of 64 distinct ASCII symbols. Panics if any symbol is
+    // repeated, is not ASCII, or is '=' or whitespace.
+    pub fn new(alphabet: &[u8; 64], padding: Padding) -> Base64 {
+        let mut decode = [INVALID; 256];
+        for (i, &c) in alphabet.iter().enumerate() {
+            assert!(c.is_ascii() && c != b'=' && !c.is_ascii_whitespace(),
+                    "base64 alphabet symbols must be ASCII and not '=' or whitespace");
+            assert!(decode[c as usize] == INVALID,
+                    "base64 alphabet symbols must be distinct");
+            decode[c as usize] = i as u8;
+        }
+        Base64 { alphabet: *alphabet, decode, padding, order: BitOrder::MsbFirst }
+    }
+
+    // The same engine, reading and writing the six bit values in the given order.
+    pub fn with_bit_order(mut self, order: BitOrder) -> Base64 {
+        self.order = order;
+        self
+    }
+
+    // RFC 4648 base64 with padding; what hex_to_base64 and base64_to_hex use.
+    pub fn standard() -> Base64 {
+        Base64::new(STANDARD_ALPHABET, Padding::Required)
+    }
+
+    // RFC 4648 base64url with padding.
+    pub fn url_safe() -> Base64 {
+        Base64::new(URL_SAFE_ALPHABET, Padding::Required)
+    }
+
+    // base64url without padding, as found in JSON Web Tokens.
+    pub fn url_safe_unpadded() -> Base64 {
+        Base64::new(URL_SAFE_ALPHABET, Padding::Forbidden)
+    }
+
+    // The crypt(3) alphabet without padding, least significant bit first, as in the hashes of
+    // MD5 and SHA crypt.
+    pub fn crypt() -> Base64 {
+        Base64::new(CRYPT_ALPHABET, Padding::Forbidden).with_bit_order(BitOrder::LsbFirst)
+    }
+
+    // The bcrypt alphabet without padding.
+    pub fn bcrypt() -> Base64 {
+        Base64::new(BCRYPT_ALPHABET, Padding::Forbidden)
+    }
+
+    pub fn alphabet(&self) -> &[u8; 64] {
+        &self.alphabet
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    pub fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+
+    // Returns the six bit value of a symbol, if it is in this engine's alphabet.
+    pub fn symbol_value(&self, symbol: u8) -> Option<u8> {
+        match self.decode[symbol as usize] {
+            INVALID => None,
+            v => Some(v),
+        }
+    }
+
+    // Takes a slice of bytes and encodes it into a base64 string.
+    pub fn encode(&self, hex: &[u8]) -> String {
+        let mut s = String::new();
+        self.encode_into(hex, &mut s);
+        s
+    }
+
+    // Like encode, but appends to an existing string so its allocation can be reused.
+    pub fn encode_into(&self, hex: &[u8], s: &mut String) {
+        s.reserve(hex.len().div_ceil(3) * 4);
+        let pad = self.padding == Padding::Required;
+        // Take six bits at a time from the array of bytes.
+        // Base64 is a set of 4x6 bits producing 4 characters.
+        let mut b = Bits::with_order(hex, 6, self.order);
+        loop {
+            let set = (b.next(), b.next(), b.next(), b.next());
+            match set {
+                (None, _, _, _) => break,
+                // If first 6 bits are present, at lest two of next 6 are as well
+                (Some(h1), Some(h2), m, l) => {
+                    s.push(self.alphabet[h1 as usize] as char);
+                    s.push(self.alphabet[h2 as usize] as char);
+                    match m {
+                        Some(m) => s.push(self.alphabet[m as usize] as char),
+                        _ if pad => s.push('='),
+                        _ => (),
+                    };
+                    match l {
+                        Some(l) => s.push(self.alphabet[l as usize] as char),
+                        _ if pad => s.push('='),
+                        _ => (),
+                    };
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // Decodes a single unbroken run of base64.
+    pub fn decode<T: AsRef<[u8]>>(&self, input: T) -> Result<Vec<u8>, DecodeError> {
+        self.decode_with(input, Base64Mode::strict())
+    }
+
+    // Decodes base64 according to mode, reporting the first problem found.
+    pub fn decode_with<T: AsRef<[u8]>>(&self, input: T, mode: Base64Mode)
+                                       -> Result<Vec<u8>, DecodeError> {
+        let input = input.as_ref();
+        // (offset, byte) of every symbol that takes part in decoding.
+        let symbols: Vec<(usize, u8)> = input.iter()
+                                             .cloned()
+                                             .enumerate()
+                                             .filter(|&(_, b)| {
+                                                 !(mode.whitespace && b.is_ascii_whitespace())
+                                             })
+                                             .collect();
+
+        let mut w = BitWriter::from_vec(Vec::with_capacity(symbols.len() / 4 * 3), self.order);
+        for (n, group) in symbols.chunks(4).enumerate() {
+            let present = self.decode_group(group, input.len(), &mut w)?;
+            if present < 4 {
+                if let Some(&(offset, _)) = symbols.get((n + 1) * 4) {
+                    return Err(DecodeError::TrailingData { offset });
+                }
+            }
+        }
+        Ok(w.into_whole_bytes())
+    }
+
+    // Decodes one group of up to four (offset, symbol) pairs into w, returning how many symbols
+    // came before any padding. Anything less than four means the input must end here, and the
+    // bits that don't make up a whole byte are left pending in w. end is the length of the whole
+    // input, which is where a short group is reported.
+    fn decode_group(&self, group: &[(usize, u8)], end: usize, w: &mut BitWriter)
+                    -> Result<usize, DecodeError> {
+        // A short final group is only allowed when padding may be left off, and a single
+        // symbol never carries a whole byte.
+        if group.len() == 1 || (group.len() < 4 && self.padding == Padding::Required) {
+            return Err(DecodeError::InvalidPadding { offset: end });
+        }
+        let mut values = [0u8; 4];
+        let mut present = group.len();
+        for (i, &(offset, b)) in group.iter().enumerate() {
+            if b == b'=' {
+                // At least two symbols are needed before padding can start.
+                if i < 2 || self.padding == Padding::Forbidden {
+                    return Err(DecodeError::InvalidPadding { offset });
+                }
+                if present == group.len() {
+                    present = i;
+                }
+                continue;
+            }
+            if present < group.len() {
+                // A symbol after padding has started in the same group, e.g. "TQ=Q".
+                return Err(DecodeError::InvalidPadding { offset });
+            }
+            match self.symbol_value(b) {
+                Some(x) => values[i] = x,
+                None => return Err(DecodeError::InvalidSymbol { byte: b, offset }),
+            }
+        }
+        for &x in values[..present].iter() {
+            w.write(x as u64, 6);
+        }
+        Ok(present)
+    }
+}
+
+impl fmt::Debug for Base64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Base64")
+         .field("alphabet", &String::from_utf8_lossy(&self.alphabet))
+         .field("padding", &self.padding)
+         .field("order", &self.order)
+         .finish()
+    }
+}
+
+// Takes a slice of bytes and encodes it into a standard padded base64 string.
+pub fn hex_to_base64(hex: &[u8]) -> String {
+    Base64::standard().encode(hex)
+}
+
+// Decodes standard base64 according to mode, reporting the first problem found.
+pub fn try_base64_to_hex_with<T: AsRef<[u8]>>(input: T, mode: Base64Mode)
+                                              -> Result<Vec<u8>, DecodeError> {
+    Base64::standard().decode_with(input, mode)
+}
+
+// Strict version of try_base64_to_hex_with.
+pub fn try_base64_to_hex<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
+    try_base64_to_hex_with(input, Base64Mode::strict())
+}
+
+// Takes a base64 encoded string and returns the vector of bytes that it decodes to.
+// Panics if the input is not valid base64; see try_base64_to_hex for a fallible version.
+pub fn base64_to_hex<T: AsRef<[u8]>>(input: T) -> Vec<u8> {
+    match try_base64_to_hex(input) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+// The base32 alphabet from RFC 4648 section 6.
+pub const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// The "extended hex" base32 alphabet from RFC 4648 section 7, which preserves sort order.
+pub const BASE32_HEX_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+// A base32 encoder and decoder for a particular alphabet and padding policy.
+//
+// Each group of five bytes is read five bits at a time, most significant bit first, giving eight
+// symbols. A final partial group of 1, 2, 3 or 4 bytes gives 2, 4, 5 or 7 symbols.
+#[derive(Clone, Copy)]
+pub struct Base32 {
+    alphabet: [u8; 32],
+    decode: [u8; 256],
+    padding: Padding,
+}
+
+impl Base32 {
+    // Creates an engine from an alphabet of 32 distinct ASCII symbols. Panics if any symbol is
+    // repeated, is not ASCII, or is '=' or whitespace.
+    pub fn new(alphabet: &[u8; 32], padding: Padding) -> Base32 {
+        let mut decode = [INVALID; 256];
+        for (i, &c) in alphabet.iter().enumerate() {
+            assert!(c.is_ascii() && c != b'=' && !c.is_ascii_whitespace(),
+                    "base32 alphabet symbols must be ASCII and not '=' or whitespace");
+            assert!(decode[c as usize] == INVALID,
+                    "base32 alphabet symbols must be distinct");
+            decode[c as usize] = i as u8;
+        }
+        Base32 { alphabet: *alphabet, decode, padding }
+    }
+
+    // RFC 4648 base32 with padding.
+    pub fn standard() -> Base32 {
+        Base32::new(BASE32_ALPHABET, Padding::Required)
+    }
+
+    // RFC 4648 base32hex with padding.
+    pub fn hex() -> Base32 {
+        Base32::new(BASE32_HEX_ALPHABET, Padding::Required)
+    }
+
+    pub fn alphabet(&self) -> &[u8; 32] {
+        &self.alphabet
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    // Returns the five bit value of a symbol, if it is in this engine's alphabet.
+    pub fn symbol_value(&self, symbol: u8) -> Option<u8> {
+        match self.decode[symbol as usize] {
+            INVALID => None,
+            v => Some(v),
+        }
+    }
+
+    // Takes a slice of bytes and encodes it into a base32 string.
+    pub fn encode(&self, hex: &[u8]) -> String {
+        let mut s = String::with_capacity(hex.len().div_ceil(5) * 8);
+        for value in Bits::new(hex, 5) {
+            s.push(self.alphabet[value as usize] as char);
+        }
+        if self.padding == Padding::Required {
+            while !s.len().is_multiple_of(8) {
+                s.push('=');
+            }
+        }
+        s
+    }
+
+    // Decodes base32, reporting the first problem found.
+    pub fn decode<T: AsRef<[u8]>>(&self, input: T) -> Result<Vec<u8>, DecodeError> {
+        let input = input.as_ref();
+        let mut w = BitWriter::from_vec(Vec::with_capacity(input.len() / 8 * 5),
+                                        BitOrder::MsbFirst);
+        for (n, group) in input.chunks(8).enumerate() {
+            let start = n * 8;
+            let present = group.iter().position(|&b| b == b'=').unwrap_or(group.len());
+            if group.len() < 8 && self.padding == Padding::Required {
+                return Err(DecodeError::InvalidPadding { offset: input.len() });
+            }
+            if present < group.len() && self.padding == Padding::Forbidden {
+                return Err(DecodeError::InvalidPadding { offset: start + present });
+            }
+            // Only these symbol counts end on a byte boundary with fewer than five spare bits.
+            match present {
+                2 | 4 | 5 | 7 | 8 => (),
+                _ if present < group.len() => {
+                    return Err(DecodeError::InvalidPadding { offset: start + present })
+                }
+                _ => return Err(DecodeError::InvalidPadding { offset: input.len() }),
+            }
+            if let Some(i) = group[present..].iter().position(|&b| b != b'=') {
+                return Err(DecodeError::InvalidPadding { offset: start + present + i });
+            }
+            if present < 8 && input.len() > start + 8 {
+                return Err(DecodeError::TrailingData { offset: start + 8 });
+            }
+
+            for (i, &b) in group[..present].iter().enumerate() {
+                match self.symbol_value(b) {
+                    Some(x) => w.write(x as u64, 5),
+                    None => return Err(DecodeError::InvalidSymbol { byte: b, offset: start + i }),
+                }
+            }
+        }
+        Ok(w.into_whole_bytes())
+    }
+}
+
+impl fmt::Debug for Base32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Base32")
+         .field("alphabet", &String::from_utf8_lossy(&self.alphabet))
+         .field("padding", &self.padding)
+         .finish()
+    }
+}
+
+// Takes a slice of bytes and encodes it into a standard padded base32 string.
+pub fn hex_to_base32(hex: &[u8]) -> String {
+    Base32::standard().encode(hex)
+}
+
+// Decodes standard padded base32.
+pub fn try_base32_to_hex<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
+    Base32::standard().decode(input)
+}
+
+// Takes a slice of bytes and encodes it as RFC 4648 base16, which is hex with the digits
+// 'A'...'F'.
+pub fn hex_to_base16(hex: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    Bits::new(hex, 4).map(|x| DIGITS[x as usize] as char).collect()
+}
+
+// Decodes RFC 4648 base16. Like most base16 decoders this also accepts lowercase digits.
+pub fn try_base16_to_hex(string: &str) -> Result<Vec<u8>, HexError> {
+    try_string_to_hex_with(string, HexMode { uppercase: true, whitespace: false })
+}
+
+// The ascii85 symbols are the 85 consecutive characters starting at '!'.
+const ASCII85_FIRST: u8 = b'!';
+
+// The Z85 alphabet from ZeroMQ RFC 32, which avoids quotes and backslashes.
+const Z85_ALPHABET: &[u8; 85] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                          .-:+=^!/*?&<>()[]{}@%$#";
+
+// Maps every byte to its Z85 digit value, or INVALID.
+const fn z85_decode_table() -> [u8; 256] {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < Z85_ALPHABET.len() {
+        table[Z85_ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+static Z85_DECODE: [u8; 256] = z85_decode_table();
+
+// Splits a big endian word into five base 85 digits, most significant first.
+fn base85_digits(mut word: u32) -> [u8; 5] {
+    let mut digits = [0u8; 5];
+    for d in digits.iter_mut().rev() {
+        *d = (word % 85) as u8;
+        word /= 85;
+    }
+    digits
+}
+
+// Folds five base 85 digits back into a word, or None if the value is more than 32 bits.
+fn base85_word(digits: &[u8; 5]) -> Option<u32> {
+    let value = digits.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+    if value > u32::MAX as u64 {
+        None
+    } else {
+        Some(value as u32)
+    }
+}
+
+// Packs up to four bytes into a big endian word, zero filling any missing low bytes.
+fn be_word(bytes: &[u8]) -> u32 {
+    (0..4).fold(0, |acc, i| (acc << 8) | *bytes.get(i).unwrap_or(&0) as u32)
+}
+
+// Takes a slice of bytes and encodes it as Adobe/btoa style ascii85, without the "<~" "~>"
+// delimiters. A group of four zero bytes is written as 'z', and a final partial group of n bytes
+// is written as n + 1 symbols.
+pub fn hex_to_ascii85(hex: &[u8]) -> String {
+    let mut s = String::with_capacity(hex.len().div_ceil(4) * 5);
+    for chunk in hex.chunks(4) {
+        let word = be_word(chunk);
+        if word == 0 && chunk.len() == 4 {
+            s.push('z');
+            continue;
+        }
+        for &d in base85_digits(word)[..chunk.len() + 1].iter() {
+            s.push((d + ASCII85_FIRST) as char);
+        }
+    }
+    s
+}
+
+// Decodes ascii85. Whitespace is skipped anywhere, and the input may optionally be wrapped in
+// "<~" and "~>" delimiters.
+pub fn try_ascii85_to_hex<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    let mut start = 0;
+    let mut end = input.len();
+    if input.starts_with(b"<~") {
+        start = 2;
+    }
+    if let Some(i) = input[start..].windows(2).position(|w| w == b"~>") {
+        end = start + i;
+        if let Some(j) = input[end + 2..].iter().position(|&b| !b.is_ascii_whitespace()) {
+            return Err(DecodeError::TrailingData { offset: end + 2 + j });
+        }
+    }
+
+    let mut v = Vec::with_capacity((end - start) / 5 * 4);
+    let mut digits = [0u8; 5];
+    let mut n = 0;
+    let mut group_start = start;
+    for (offset, &b) in input.iter().enumerate().take(end).skip(start) {
+        if b.is_ascii_whitespace() {
+            continue;
+        }
+        if n == 0 {
+            group_start = offset;
+        }
+        if b == b'z' && n == 0 {
+            v.extend_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+        if !(ASCII85_FIRST..ASCII85_FIRST + 85).contains(&b) {
+            return Err(DecodeError::InvalidSymbol { byte: b, offset });
+        }
+        digits[n] = b - ASCII85_FIRST;
+        n += 1;
+        if n == 5 {
+            match base85_word(&digits) {
+                Some(word) => v.extend_from_slice(&word.to_be_bytes()),
+                None => return Err(DecodeError::Overflow { offset: group_start }),
+            }
+            n = 0;
+        }
+    }
+    if n == 1 {
+        return Err(DecodeError::InvalidPadding { offset: end });
+    }
+    if n > 1 {
+        // Pad with the highest digit so truncation rounds back to the encoded bytes.
+        for d in digits[n..].iter_mut() {
+            *d = 84;
+        }
+        match base85_word(&digits) {
+            Some(word) => v.extend_from_slice(&word.to_be_bytes()[..n - 1]),
+            None => return Err(DecodeError::Overflow { offset: group_start }),
+        }
+    }
+    Ok(v)
+}
+
+// Takes a slice of bytes whose length is a multiple of four and encodes it as Z85. Returns None
+// for other lengths, which Z85 can't represent.
+pub fn hex_to_z85(hex: &[u8]) -> Option<String> {
+    if !hex.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut s = String::with_capacity(hex.len() / 4 * 5);
+    for chunk in hex.chunks(4) {
+        for &d in base85_digits(be_word(chunk)).iter() {
+            s.push(Z85_ALPHABET[d as usize] as char);
+        }
+    }
+    Some(s)
+}
+
+// Decodes Z85, whose length must be a multiple of five.
+pub fn try_z85_to_hex<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    if input.len() % 5 != 0 {
+        return Err(DecodeError::InvalidPadding { offset: input.len() - input.len() % 5 });
+    }
+    let mut v = Vec::with_capacity(input.len() / 5 * 4);
+    for (n, group) in input.chunks(5).enumerate() {
+        let mut digits = [0u8; 5];
+        for (i, &b) in group.iter().enumerate() {
+            match Z85_DECODE[b as usize] {
+                INVALID => return Err(DecodeError::InvalidSymbol { byte: b, offset: n * 5 + i }),
+                d => digits[i] = d,
+            }
+        }
+        match base85_word(&digits) {
+            Some(word) => v.extend_from_slice(&word.to_be_bytes()),
+            None => return Err(DecodeError::Overflow { offset: n * 5 }),
+        }
+    }
+    Ok(v)
+}
+
+#[test]
+fn test_hex_and_string() {
+    let h = &[0x0, 0x12, 0x34, 0xab, 0xcd, 0xef, 0xf];
+    let s = "001234abcdef0f";
+    assert_eq!(hex_to_string(h), s);
+    assert_eq!(string_to_hex(s), h);
+
+    let h = &[0x0];
+    let s = "00";
+    assert_eq!(hex_to_string(h), s);
+    assert_eq!(string_to_hex(s), h);
+
+    let h = &[];
+    let s = "";
+    assert_eq!(hex_to_string(h), s);
+    assert_eq!(string_to_hex(s), h);
+}
+
+#[test]
+fn test_hex_errors() {
+    assert_eq!(try_string_to_hex("00ff"), Ok(vec![0x00, 0xff]));
+    assert_eq!(try_string_to_hex("0g"), Err(HexError::InvalidChar { c: 'g', offset: 1 }));
+    assert_eq!(try_string_to_hex("abc"), Err(HexError::OddLength(3)));
+
+    // Uppercase and whitespace are only accepted when asked for.
+    assert_eq!(try_string_to_hex("AB"), Err(HexError::InvalidChar { c: 'A', offset: 0 }));
+    assert_eq!(try_string_to_hex("ab cd"), Err(HexError::InvalidChar { c: ' ', offset: 2 }));
+
+    let upper = HexMode { uppercase: true, whitespace: false };
+    assert_eq!(try_string_to_hex_with("AbCd", upper), Ok(vec![0xab, 0xcd]));
+    assert_eq!(try_string_to_hex_with("ab cd", upper),
+               Err(HexError::InvalidChar { c: ' ', offset: 2 }));
+
+    let lenient = HexMode::lenient();
+    assert_eq!(try_string_to_hex_with("4D 61\n6e\r\n", lenient), Ok(vec![0x4d, 0x61, 0x6e]));
+    assert_eq!(try_string_to_hex_with("a b c", lenient), Err(HexError::OddLength(3)));
+    assert_eq!(try_string_to_hex_with("ab\nxz", lenient),
+               Err(HexError::InvalidChar { c: 'x', offset: 3 }));
+}
+
+#[test]
+fn test_hex_into() {
+    let mut s = "prefix:".to_string();
+    hex_to_string_into(&[0xde, 0xad], &mut s);
+    hex_to_string_into(&[0xbe, 0xef], &mut s);
+    assert_eq!(s, "prefix:deadbeef");
+
+    let mut v = vec![0x01];
+    assert_eq!(try_string_to_hex_into("02ff", HexMode::strict(), &mut v), Ok(()));
+    assert_eq!(try_string_to_hex_into("0 3", HexMode::lenient(), &mut v), Ok(()));
+    assert_eq!(v, vec![0x01, 0x02, 0xff, 0x03]);
+
+    // Nothing is appended on error, even if the problem is well into the input.
+    assert_eq!(try_string_to_hex_into("0405x6", HexMode::strict(), &mut v),
+               Err(HexError::InvalidChar { c: 'x', offset: 4 }));
+    assert_eq!(try_string_to_hex_into("04 05 0", HexMode::lenient(), &mut v),
+               Err(HexError::OddLength(5)));
+    assert_eq!(v, vec![0x01, 0x02, 0xff, 0x03]);
+
+    // Non-ASCII characters are reported whole, at their byte offset.
+    assert_eq!(try_string_to_hex("00é0"), Err(HexError::InvalidChar { c: 'é', offset: 2 }));
+    assert_eq!(try_string_to_hex_with("0 é", HexMode::lenient()),
+               Err(HexError::InvalidChar { c: 'é', offset: 2 }));
+
+    // Every byte survives the round trip through the tables.
+    let b: Vec<u8> = (0..=255).collect();
+    assert_eq!(string_to_hex(&hex_to_string(&b)), b);
+    assert_eq!(try_base16_to_hex(&hex_to_base16(&b)), Ok(b));
+}
+
+#[test]
+#[should_panic(expected = "invalid hex character 'G' at offset 2")]
+fn test_string_to_hex_panics() {
+    string_to_hex("00G0");
+}
+
+#[test]
+fn test_base64() {
+    // From wikipedia base64 examples
+    let b = &[0x4d, 0x61, 0x6e];
+    let s = "TWFu".to_string();
+    assert_eq!(hex_to_base64(b), s);
+    assert_eq!(base64_to_hex(s), b);
+
+    let b = "leasure.".as_bytes();
+    let s = "bGVhc3VyZS4=".to_string();
+    assert_eq!(hex_to_base64(b), s);
+    assert_eq!(base64_to_hex(s), b);
+
+    let b = "any carnal pleas".as_bytes();
+    let s = "YW55IGNhcm5hbCBwbGVhcw==".to_string();
+    assert_eq!(hex_to_base64(b), s);
+    assert_eq!(base64_to_hex(s), b);
+
+    let b = "Man is distinguished, not only by his reason, but by this singular passion from \
+             other animals, which is a lust of the mind, that by a perseverance of delight in the \
+             continued and indefatigable generation of knowledge, exceeds the short vehemence of \
+             any carnal pleasure."
+                .as_bytes();
+
+    let s = "TWFuIGlzIGRpc3Rpbmd1aXNoZWQsIG5vdCBvbmx5IGJ5IGhpcyByZWFzb24sIGJ1dCBieSB0aGlzIHNpbmd1b\
+             GFyIHBhc3Npb24gZnJvbSBvdGhlciBhbmltYWxzLCB3aGljaCBpcyBhIGx1c3Qgb2YgdGhlIG1pbmQsIHRoYX\
+             QgYnkgYSBwZXJzZXZlcmFuY2Ugb2YgZGVsaWdodCBpbiB0aGUgY29udGludWVkIGFuZCBpbmRlZmF0aWdhYmx\
+             lIGdlbmVyYXRpb24gb2Yga25vd2xlZGdlLCBleGNlZWRzIHRoZSBzaG9ydCB2ZWhlbWVuY2Ugb2YgYW55IGNh\
+             cm5hbCBwbGVhc3VyZS4="
+                .to_string();
+
+    assert_eq!(hex_to_base64(b), s);
+    assert_eq!(String::from_utf8(base64_to_hex(s)).unwrap(),
+               String::from_utf8(b.to_owned()).unwrap());
+}
+
+#[test]
+fn test_base64_errors() {
+    assert_eq!(try_base64_to_hex("TWFu"), Ok(b"Man".to_vec()));
+    assert_eq!(try_base64_to_hex(&b"TQ=="[..]), Ok(b"M".to_vec()));
+    assert_eq!(try_base64_to_hex(""), Ok(vec![]));
+
+    assert_eq!(try_base64_to_hex("TW*u"),
+               Err(DecodeError::InvalidSymbol { byte: b'*', offset: 2 }));
+    assert_eq!(try_base64_to_hex("TWF"), Err(DecodeError::InvalidPadding { offset: 3 }));
+    assert_eq!(try_base64_to_hex("T==="), Err(DecodeError::InvalidPadding { offset: 1 }));
+    assert_eq!(try_base64_to_hex("TQ=Q"), Err(DecodeError::InvalidPadding { offset: 3 }));
+    assert_eq!(try_base64_to_hex("TQ==TWFu"), Err(DecodeError::TrailingData { offset: 4 }));
+
+    // Whitespace is only skipped in lenient mode.
+    assert_eq!(try_base64_to_hex("TW\nFu"),
+               Err(DecodeError::InvalidSymbol { byte: b'\n', offset: 2 }));
+    let lenient = Base64Mode::lenient();
+    assert_eq!(try_base64_to_hex_with("TW\nFu\r\n bGVh\n", lenient), Ok(b"Manlea".to_vec()));
+    assert_eq!(try_base64_to_hex_with("TQ==\n", lenient), Ok(b"M".to_vec()));
+    assert_eq!(try_base64_to_hex_with("TQ==\nx", lenient),
+               Err(DecodeError::TrailingData { offset: 5 }));
+}
+
+#[test]
+#[should_panic(expected = "invalid padding at offset 2")]
+fn test_base64_to_hex_panics() {
+    base64_to_hex("TW");
+}
+
+#[test]
+fn test_base64_variants() {
+    // RFC 4648 section 10 test vectors, in each padding style.
+    let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="),
+                   ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+    let unpadded = Base64::new(STANDARD_ALPHABET, Padding::Forbidden);
+    let optional = Base64::new(STANDARD_ALPHABET, Padding::Optional);
+    for &(plain, encoded) in vectors.iter() {
+        let trimmed = encoded.trim_end_matches('=');
+        assert_eq!(Base64::standard().encode(plain.as_bytes()), encoded);
+        assert_eq!(Base64::standard().decode(encoded), Ok(plain.as_bytes().to_vec()));
+        assert_eq!(unpadded.encode(plain.as_bytes()), trimmed);
+        assert_eq!(unpadded.decode(trimmed), Ok(plain.as_bytes().to_vec()));
+        assert_eq!(optional.decode(encoded), Ok(plain.as_bytes().to_vec()));
+        assert_eq!(optional.decode(trimmed), Ok(plain.as_bytes().to_vec()));
+    }
+    assert_eq!(Base64::standard().decode("Zg"), Err(DecodeError::InvalidPadding { offset: 2 }));
+    assert_eq!(unpadded.decode("Zg=="), Err(DecodeError::InvalidPadding { offset: 2 }));
+    assert_eq!(unpadded.decode("Zm9vY"), Err(DecodeError::InvalidPadding { offset: 5 }));
+
+    // base64url swaps the last two symbols.
+    let b = &[0xfb, 0xff, 0xbf];
+    assert_eq!(Base64::standard().encode(b), "+/+/");
+    assert_eq!(Base64::url_safe().encode(b), "-_-_");
+    assert_eq!(Base64::url_safe().decode("-_-_"), Ok(b.to_vec()));
+    assert_eq!(Base64::url_safe().decode("+/+/"),
+               Err(DecodeError::InvalidSymbol { byte: b'+', offset: 0 }));
+
+    // The header of the example JWT from RFC 7515 section 3.3.
+    let jwt = Base64::url_safe_unpadded();
+    let header = b"{\"typ\":\"JWT\",\r\n \"alg\":\"HS256\"}";
+    assert_eq!(jwt.encode(header), "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9");
+    assert_eq!(jwt.decode("eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9"), Ok(header.to_vec()));
+
+    assert_eq!(Base64::crypt().encode(&[0x00, 0x00, 0x00]), "....");
+    assert_eq!(Base64::crypt().encode(&[0xff]), "z1");
+    assert_eq!(Base64::crypt().decode("z1"), Ok(vec![0xff]));
+    // The digest of $1$saltsalt$qjXMvbEw8oaL.CzflDtaK/, MD5 crypt of "password", taken in the
+    // order MD5 crypt encodes its bytes: 12, 6, 0, 13, 7, 1, 14, 8, 2, 15, 9, 3, 5, 10, 4, 11.
+    let digest = [0xf6, 0x3b, 0x62, 0xfb, 0x09, 0xf1, 0x0a, 0x6d, 0x5e, 0x80, 0xf3, 0xaf, 0xf1,
+                  0x93, 0x9b, 0x56];
+    assert_eq!(Base64::crypt().encode(&digest), "qjXMvbEw8oaL.CzflDtaK/");
+    assert_eq!(Base64::crypt().decode("qjXMvbEw8oaL.CzflDtaK/"), Ok(digest.to_vec()));
+    assert_eq!(Base64::bcrypt().encode(&[0xff]), "9u");
+    assert_eq!(Base64::bcrypt().decode("9u"), Ok(vec![0xff]));
+}
+
+#[test]
+#[should_panic(expected = "distinct")]
+fn test_base64_alphabet_must_be_distinct() {
+    let mut alphabet = *STANDARD_ALPHABET;
+    alphabet[1] = b'A';
+    Base64::new(&alphabet, Padding::Required);
+}
+
+#[test]
+fn test_base32() {
+    // RFC 4648 section 10 test vectors.
+    let vectors = [("", "", ""),
+                   ("f", "MY======", "CO======"),
+                   ("fo", "MZXQ====", "CPNG===="),
+                   ("foo", "MZXW6===", "CPNMU==="),
+                   ("foob", "MZXW6YQ=", "CPNMUOG="),
+                   ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+                   ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======")];
+    let unpadded = Base32::new(BASE32_ALPHABET, Padding::Forbidden);
+    for &(plain, encoded, hex_encoded) in vectors.iter() {
+        assert_eq!(hex_to_base32(plain.as_bytes()), encoded);
+        assert_eq!(try_base32_to_hex(encoded), Ok(plain.as_bytes().to_vec()));
+        assert_eq!(Base32::hex().encode(plain.as_bytes()), hex_encoded);
+        assert_eq!(Base32::hex().decode(hex_encoded), Ok(plain.as_bytes().to_vec()));
+        let trimmed = encoded.trim_end_matches('=');
+        assert_eq!(unpadded.encode(plain.as_bytes()), trimmed);
+        assert_eq!(unpadded.decode(trimmed), Ok(plain.as_bytes().to_vec()));
+    }
+
+    let b: Vec<u8> = (0..=255).collect();
+    assert_eq!(try_base32_to_hex(hex_to_base32(&b)), Ok(b.clone()));
+    assert_eq!(Base32::hex().decode(Base32::hex().encode(&b)), Ok(b));
+
+    assert_eq!(try_base32_to_hex("MZXW6"), Err(DecodeError::InvalidPadding { offset: 5 }));
+    assert_eq!(try_base32_to_hex("MZX====="), Err(DecodeError::InvalidPadding { offset: 3 }));
+    assert_eq!(try_base32_to_hex("MY====A="), Err(DecodeError::InvalidPadding { offset: 6 }));
+    assert_eq!(try_base32_to_hex("MY======MZXW6==="),
+               Err(DecodeError::TrailingData { offset: 8 }));
+    assert_eq!(try_base32_to_hex("my======"),
+               Err(DecodeError::InvalidSymbol { byte: b'm', offset: 0 }));
+    assert_eq!(unpadded.decode("MY======"), Err(DecodeError::InvalidPadding { offset: 2 }));
+}
+
+#[test]
+fn test_base16() {
+    assert_eq!(hex_to_base16(b"foobar"), "666F6F626172");
+    assert_eq!(hex_to_base16(&[0xab, 0xcd, 0x0f]), "ABCD0F");
+    assert_eq!(hex_to_base16(&[]), "");
+    assert_eq!(try_base16_to_hex("666F6F626172"), Ok(b"foobar".to_vec()));
+    assert_eq!(try_base16_to_hex("ABCD0f"), Ok(vec![0xab, 0xcd, 0x0f]));
+    assert_eq!(try_base16_to_hex("ABC"), Err(HexError::OddLength(3)));
+}
+
+#[test]
+fn test_ascii85() {
+    // From the wikipedia ascii85 examples.
+    assert_eq!(hex_to_ascii85(b"Man "), "9jqo^");
+    assert_eq!(hex_to_ascii85(b"sure."), "F*2M7/c");
+    assert_eq!(hex_to_ascii85(&[0, 0, 0, 0, 0]), "z!!");
+    assert_eq!(try_ascii85_to_hex("9jqo^"), Ok(b"Man ".to_vec()));
+    assert_eq!(try_ascii85_to_hex("s8W-!"), Ok(vec![0xff, 0xff, 0xff, 0xff]));
+    assert_eq!(try_ascii85_to_hex("<~F*2M7\n/c~>"), Ok(b"sure.".to_vec()));
+    assert_eq!(try_ascii85_to_hex("z!!"), Ok(vec![0, 0, 0, 0, 0]));
+
+    let b = "Man is distinguished, not only by his reason, but by this singular passion from \
+             other animals, which is a lust of the mind, that by a perseverance of delight in the \
+             continued and indefatigable generation of knowledge, exceeds the short vehemence of \
+             any carnal pleasure."
+                .as_bytes();
+    let s = "9jqo^BlbD-BleB1DJ+*+F(f,q/0JhKF<GL>Cj@.4Gp$d7F!,L7@<6@)/0JDEF<G%<+EV:2F!,O<DJ+*.@<*K0\
+             @<6L(Df-\\0Ec5e;DffZ(EZee.Bl.9pF\"AGXBPCsi+DGm>@3BB/F*&OCAfu2/AKYi(DIb:@FD,*)+C]U=@3BN\
+             #EcYf8ATD3s@q?d$AftVqCh[NqF<G:8+EV:.+Cf>-FD5W8ARlolDIal(DId<j@<?3r@:F%a+D58'ATD4$Bl@l3\
+             De:,-DJs`8ARoFb/0JMK@qB4^F!,R<AKZ&-DfTqBG%G>uD.RTpAKYo'+CT/5+Cei#DII?(E,9)oF*2M7/c";
+    assert_eq!(hex_to_ascii85(b), s);
+    assert_eq!(try_ascii85_to_hex(s), Ok(b.to_vec()));
+
+    let b: Vec<u8> = (0..=255).collect();
+    assert_eq!(try_ascii85_to_hex(hex_to_ascii85(&b)), Ok(b));
+
+    assert_eq!(try_ascii85_to_hex("9jqo^F"), Err(DecodeError::InvalidPadding { offset: 6 }));
+    assert_eq!(try_ascii85_to_hex("9jqzo^"),
+               Err(DecodeError::InvalidSymbol { byte: b'z', offset: 3 }));
+    assert_eq!(try_ascii85_to_hex("9j{o^"),
+               Err(DecodeError::InvalidSymbol { byte: b'{', offset: 2 }));
+    assert_eq!(try_ascii85_to_hex("s8W-!s8W-\""), Err(DecodeError::Overflow { offset: 5 }));
+    assert_eq!(try_ascii85_to_hex("<~9jqo^~>x"), Err(DecodeError::TrailingData { offset: 9 }));
+}
+
+#[test]
+fn test_z85() {
+    // From the ZeroMQ RFC 32 reference implementation.
+    let b = &[0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+    assert_eq!(hex_to_z85(b), Some("HelloWorld".to_string()));
+    assert_eq!(try_z85_to_hex("HelloWorld"), Ok(b.to_vec()));
+    assert_eq!(hex_to_z85(&[1, 2, 3]), None);
+
+    let b: Vec<u8> = (0..=255).collect();
+    assert_eq!(try_z85_to_hex(hex_to_z85(&b).unwrap()), Ok(b));
+
+    assert_eq!(try_z85_to_hex("Hello"), Ok(vec![0x86, 0x4f, 0xd2, 0x6f]));
+    assert_eq!(try_z85_to_hex("HelloWor"), Err(DecodeError::InvalidPadding { offset: 5 }));
+    assert_eq!(try_z85_to_hex("Hel\"o"),
+               Err(DecodeError::InvalidSymbol { byte: b'"', offset: 3 }));
+    assert_eq!(try_z85_to_hex("#####"), Err(DecodeError::Overflow { offset: 0 }));
+}
+
+#[test]
+fn test_bits() {
+    let h = &[0x12];
+    let mut b = Bits::new(h, 8);
+    assert_eq!(b.next(), Some(0x12));
+    assert_eq!(b.next(), None);
+
+    let mut b = Bits::new(h, 4);
+    assert_eq!(b.next(), Some(0x1));
+    assert_eq!(b.next(), Some(0x2));
+    assert_eq!(b.next(), None);
+
+    let h = &[0b11111101, 0b11111011];
+    let mut b = Bits::new(h, 6);
+    assert_eq!(b.next(), Some(0b111111));
+    assert_eq!(b.next(), Some(0b011111));
+    assert_eq!(b.next(), Some(0b101100));
+    assert_eq!(b.next(), None);
+}
@@ -0,0 +1,457 @@
+// Streaming versions of the hex and base64 codecs. The writers encode whatever is written to them
+// and the readers decode whatever they read, a chunk at a time, so memory use stays bounded no
+// matter how much data passes through. Output is byte for byte what the one-shot functions in
+// conversions produce for the same input, and decoding errors carry the same offsets.
+
+use std::cmp;
+use std::error::Error;
+use std::io::{self, Read, Write};
+use conversions::{hex_digit, hex_to_string_into, Base64, Base64Mode, BitWriter,
+                  DecodeError, HexError, HexMode};
+
+// Size of the chunks read from the inner reader or encoded per write call.
+const CHUNK: usize = 8192;
+
+fn invalid_data<E: Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// Wraps a writer so that bytes written to it are passed on as lowercase hex.
+pub struct HexWriter<W: Write> {
+    inner: W,
+    buf: String,
+}
+
+impl<W: Write> HexWriter<W> {
+    pub fn new(inner: W) -> HexWriter<W> {
+        HexWriter { inner, buf: String::new() }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for HexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), CHUNK);
+        self.buf.clear();
+        hex_to_string_into(&buf[..n], &mut self.buf);
+        self.inner.write_all(self.buf.as_bytes())?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Wraps a writer so that bytes written to it are passed on as base64.
+//
+// Bytes are held back until a whole group of three is available, so the final partial group and
+// any padding are only written by finish. If the writer is dropped without calling finish this
+// happens on drop, but any error writing them is lost.
+pub struct Base64Writer<W: Write> {
+    inner: Option<W>,
+    engine: Base64,
+    // The bytes of an incomplete group held back from earlier writes.
+    pending: Vec<u8>,
+    buf: String,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub fn new(inner: W, engine: Base64) -> Base64Writer<W> {
+        Base64Writer {
+            inner: Some(inner),
+            engine,
+            pending: Vec::with_capacity(3),
+            buf: String::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    // Writes out the final partial group and padding, and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_pending()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        self.buf.clear();
+        self.engine.encode_into(&self.pending, &mut self.buf);
+        self.pending.clear();
+        self.inner.as_mut().unwrap().write_all(self.buf.as_bytes())
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), CHUNK);
+        let mut input = &buf[..n];
+        self.buf.clear();
+        let held = self.pending.len();
+        if held > 0 {
+            let take = cmp::min(3 - held, input.len());
+            if held + take < 3 {
+                self.pending.extend_from_slice(&input[..take]);
+                return Ok(n);
+            }
+            let mut group = [0; 3];
+            group[..held].copy_from_slice(&self.pending);
+            group[held..].copy_from_slice(&input[..take]);
+            self.engine.encode_into(&group, &mut self.buf);
+            input = &input[take..];
+        }
+        let whole = input.len() / 3 * 3;
+        self.engine.encode_into(&input[..whole], &mut self.buf);
+        self.inner.as_mut().unwrap().write_all(self.buf.as_bytes())?;
+        self.pending.clear();
+        self.pending.extend_from_slice(&input[whole..]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for Base64Writer<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_pending();
+        }
+    }
+}
+
+// The incremental part of a decoding reader, fed the encoded input one chunk at a time.
+trait Decode {
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> io::Result<()>;
+    fn finish(&mut self, out: &mut Vec<u8>) -> io::Result<()>;
+}
+
+// Reads encoded chunks from inner and serves the decoded bytes.
+struct DecodeReader<R: Read, D: Decode> {
+    inner: R,
+    decoder: D,
+    buf: Vec<u8>,
+    out: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read, D: Decode> DecodeReader<R, D> {
+    fn new(inner: R, decoder: D) -> DecodeReader<R, D> {
+        DecodeReader {
+            inner,
+            decoder,
+            buf: vec![0; CHUNK],
+            out: Vec::with_capacity(CHUNK),
+            pos: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read, D: Decode> Read for DecodeReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.pos == self.out.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.out.clear();
+            self.pos = 0;
+            let n = self.inner.read(&mut self.buf)?;
+            if n == 0 {
+                self.done = true;
+                self.decoder.finish(&mut self.out)?;
+            } else {
+                self.decoder.update(&self.buf[..n], &mut self.out)?;
+            }
+        }
+        let n = cmp::min(buf.len(), self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+struct HexDecoder {
+    mode: HexMode,
+    high: Option<u8>,
+    digits: usize,
+    offset: usize,
+}
+
+impl Decode for HexDecoder {
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+        for &b in chunk {
+            let offset = self.offset;
+            self.offset += 1;
+            if self.mode.whitespace && b.is_ascii_whitespace() {
+                continue;
+            }
+            let nibble = match hex_digit(b as char, self.mode.uppercase) {
+                Some(n) => n,
+                None => {
+                    return Err(invalid_data(HexError::InvalidChar {
+                        c: b as char,
+                        offset,
+                    }))
+                }
+            };
+            self.digits += 1;
+            match self.high.take() {
+                Some(h) => out.push((h << 4) | nibble),
+                None => self.high = Some(nibble),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _: &mut Vec<u8>) -> io::Result<()> {
+        if self.high.is_some() {
+            return Err(invalid_data(HexError::OddLength(self.digits)));
+        }
+        Ok(())
+    }
+}
+
+// Wraps a reader of hex text and reads the bytes it decodes to.
+//
+// Errors are io::Errors of kind InvalidData wrapping a HexError. Input is treated as bytes rather
+// than UTF-8, so a non-ASCII character is reported by its first byte. The reader should not be
+// used after it returns an error.
+pub struct HexReader<R: Read>(DecodeReader<R, HexDecoder>);
+
+impl<R: Read> HexReader<R> {
+    pub fn new(inner: R, mode: HexMode) -> HexReader<R> {
+        HexReader(DecodeReader::new(inner,
+                                    HexDecoder {
+                                        mode,
+                                        high: None,
+                                        digits: 0,
+                                        offset: 0,
+                                    }))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.0.inner
+    }
+}
+
+impl<R: Read> Read for HexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+struct Base64Decoder {
+    engine: Base64,
+    mode: Base64Mode,
+    group: Vec<(usize, u8)>,
+    bits: BitWriter,
+    offset: usize,
+    // Set once a padded group has been seen, after which only whitespace may follow.
+    ended: bool,
+}
+
+impl Decode for Base64Decoder {
+    fn update(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+        for &b in chunk {
+            let offset = self.offset;
+            self.offset += 1;
+            if self.mode.whitespace && b.is_ascii_whitespace() {
+                continue;
+            }
+            if self.ended {
+                return Err(invalid_data(DecodeError::TrailingData { offset }));
+            }
+            self.group.push((offset, b));
+            if self.group.len() == 4 {
+                let present = self.engine
+                                  .decode_group(&self.group, self.offset, &mut self.bits)
+                                  .map_err(invalid_data)?;
+                self.group.clear();
+                self.ended = present < 4;
+            }
+        }
+        out.extend_from_slice(&self.bits.take_bytes());
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        if !self.group.is_empty() {
+            self.engine
+                .decode_group(&self.group, self.offset, &mut self.bits)
+                .map_err(invalid_data)?;
+        }
+        // Bits left over from a short final group don't make up a byte and are dropped.
+        out.extend_from_slice(&self.bits.take_bytes());
+        Ok(())
+    }
+}
+
+// Wraps a reader of base64 text and reads the bytes it decodes to.
+//
+// Errors are io::Errors of kind InvalidData wrapping a DecodeError. The reader should not be used
+// after it returns an error.
+pub struct Base64Reader<R: Read>(DecodeReader<R, Base64Decoder>);
+
+impl<R: Read> Base64Reader<R> {
+    pub fn new(inner: R, engine: Base64, mode: Base64Mode) -> Base64Reader<R> {
+        Base64Reader(DecodeReader::new(inner,
+                                       Base64Decoder {
+                                           engine,
+                                           mode,
+                                           group: Vec::with_capacity(4),
+                                           bits: BitWriter::new(engine.bit_order()),
+                                           offset: 0,
+                                           ended: false,
+                                       }))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.0.inner
+    }
+}
+
+impl<R: Read> Read for Base64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{self, Read, Write};
+    use conversions::{hex_to_string, try_string_to_hex_with, Base64, Base64Mode, DecodeError,
+                      HexError, HexMode, Padding, STANDARD_ALPHABET};
+
+    // A reader that hands out at most step bytes per read, to exercise chunk boundaries.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = *[buf.len(), self.step, self.data.len()].iter().min().unwrap();
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn read_all<R: Read>(mut r: R) -> Result<Vec<u8>, io::Error> {
+        let mut v = Vec::new();
+        r.read_to_end(&mut v)?;
+        Ok(v)
+    }
+
+    fn inner_error<E: 'static + ::std::error::Error + Copy>(e: io::Error) -> E {
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        *e.get_ref().unwrap().downcast_ref::<E>().unwrap()
+    }
+
+    #[test]
+    fn test_hex_writer() {
+        let data: Vec<u8> = (0..100u32).map(|x| (x * 7) as u8).collect();
+        for step in 1..8 {
+            let mut w = HexWriter::new(Vec::new());
+            for chunk in data.chunks(step) {
+                w.write_all(chunk).unwrap();
+            }
+            assert_eq!(String::from_utf8(w.into_inner()).unwrap(), hex_to_string(&data));
+        }
+    }
+
+    #[test]
+    fn test_base64_writer() {
+        let data: Vec<u8> = (0..20).map(|x| x * 13).collect();
+        let engines = [Base64::standard(),
+                       Base64::new(STANDARD_ALPHABET, Padding::Forbidden),
+                       Base64::crypt()];
+        for engine in engines.iter() {
+            for len in 0..data.len() {
+                for step in 1..5 {
+                    let mut w = Base64Writer::new(Vec::new(), *engine);
+                    for chunk in data[..len].chunks(step) {
+                        w.write_all(chunk).unwrap();
+                    }
+                    let encoded = String::from_utf8(w.finish().unwrap()).unwrap();
+                    assert_eq!(encoded, engine.encode(&data[..len]));
+                }
+            }
+        }
+
+        // Dropping the writer still writes out the last group.
+        let mut out = Vec::new();
+        {
+            let mut w = Base64Writer::new(&mut out, Base64::standard());
+            w.write_all(b"leasure.").unwrap();
+        }
+        assert_eq!(out, b"bGVhc3VyZS4=");
+    }
+
+    #[test]
+    fn test_hex_reader() {
+        let text = "00 1f\n2E3d\r\nff";
+        for step in 1..5 {
+            let r = HexReader::new(Trickle { data: text.as_bytes(), step },
+                                   HexMode::lenient());
+            assert_eq!(read_all(r).unwrap(),
+                       try_string_to_hex_with(text, HexMode::lenient()).unwrap());
+        }
+
+        for &(text, err) in [("001g", HexError::InvalidChar { c: 'g', offset: 3 }),
+                             ("00112", HexError::OddLength(5)),
+                             ("0 0", HexError::InvalidChar { c: ' ', offset: 1 })]
+                                .iter() {
+            assert_eq!(try_string_to_hex_with(text, HexMode::strict()), Err(err));
+            let r = HexReader::new(Trickle { data: text.as_bytes(), step: 2 }, HexMode::strict());
+            assert_eq!(inner_error::<HexError>(read_all(r).unwrap_err()), err);
+        }
+    }
+
+    #[test]
+    fn test_base64_reader() {
+        let text = include_str!("../challenges/data/6.txt");
+        let expected = Base64::standard().decode_with(text, Base64Mode::lenient()).unwrap();
+        for &step in [1, 3, 4, 61, 4096].iter() {
+            let r = Base64Reader::new(Trickle { data: text.as_bytes(), step },
+                                      Base64::standard(),
+                                      Base64Mode::lenient());
+            assert_eq!(read_all(r).unwrap(), expected);
+        }
+
+        let lenient = Base64Mode::lenient();
+        for &(text, err) in [("TW*u", DecodeError::InvalidSymbol { byte: b'*', offset: 2 }),
+                             ("TWF", DecodeError::InvalidPadding { offset: 3 }),
+                             ("TQ=Q", DecodeError::InvalidPadding { offset: 3 }),
+                             ("TQ==\n x", DecodeError::TrailingData { offset: 6 })]
+                                .iter() {
+            assert_eq!(Base64::standard().decode_with(text, lenient), Err(err));
+            let r = Base64Reader::new(Trickle { data: text.as_bytes(), step: 1 },
+                                      Base64::standard(),
+                                      lenient);
+            assert_eq!(inner_error::<DecodeError>(read_all(r).unwrap_err()), err);
+        }
+
+        let hash = "qjXMvbEw8oaL.CzflDtaK/";
+        let r = Base64Reader::new(Trickle { data: hash.as_bytes(), step: 3 },
+                                  Base64::crypt(),
+                                  Base64Mode::strict());
+        assert_eq!(read_all(r).unwrap(), Base64::crypt().decode(hash).unwrap());
+    }
+}
@@ -0,0 +1,684 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+use text::Scorer;
+
+pub mod search;
+
+pub use self::search::{Search, Step};
+
+// A possible single byte xor key, how far the message it gives is from English (lower is better),
+// and the message itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub key: u8,
+    pub score: f32,
+    pub plaintext: Vec<u8>,
+}
+
+// The amount of work, roughly in bytes looked at, below which the crack functions run their
+// searches on the calling thread. Starting worker threads costs more than they'd save on short
+// messages and small keyspaces.
+const PARALLEL_WORK: usize = 1 << 20;
+
+fn default_search<'a>(work: usize) -> Search<'a> {
+    if work < PARALLEL_WORK {
+        Search::sequential()
+    } else {
+        Search::new()
+    }
+}
+
+// Orders scores lowest first. A scorer can return NaN for input it makes nothing of, so NaN goes
+// last, whatever its sign.
+fn by_score(a: f32, b: f32) -> Ordering {
+    a.is_nan().cmp(&b.is_nan()).then(a.total_cmp(&b))
+}
+
+// Tries every single byte key against m and returns the n best candidates, best first. Every key
+// is a candidate, however unlikely its plaintext, unless m is empty, when there are none.
+pub fn rank_xor_keys(m: &[u8], n: usize) -> Vec<Candidate> {
+    use text::CharFreq;
+
+    rank_xor_keys_with(m, n, &CharFreq::for_english())
+}
+
+// Like rank_xor_keys, but scoring plaintexts with the given scorer rather than by English letter
+// frequencies.
+pub fn rank_xor_keys_with<S: Scorer + ?Sized>(m: &[u8], n: usize, scorer: &S) -> Vec<Candidate> {
+    rank_xor_keys_on(&default_search(256 * m.len()), m, n, scorer)
+}
+
+// Like rank_xor_keys_with, running on the given search, whose keys are the 256 byte values. If
+// the search is cancelled the candidates are the best of the keys tried.
+pub fn rank_xor_keys_on<S>(search: &Search, m: &[u8], n: usize, scorer: &S) -> Vec<Candidate>
+    where S: Scorer + ?Sized
+{
+    use combine::xor_byte;
+
+    if m.is_empty() {
+        return vec![];
+    }
+    let keys: Vec<u8> = (0..=255).collect();
+    let mut candidates = search.run(&keys, |&b| {
+        let plaintext = xor_byte(m, b);
+        Step::Continue(Candidate { key: b, score: scorer.score(&plaintext), plaintext })
+    });
+    // Stable, so ties go to the lower key.
+    candidates.sort_by(|a, b| by_score(a.score, b.score));
+    candidates.truncate(n);
+    candidates
+}
+
+// The single byte key most likely to have been xored with m, or 0 if m is empty.
+pub fn find_xor_key(m: &[u8]) -> u8 {
+    rank_xor_keys(m, 1).first().map_or(0, |c| c.key)
+}
+
+// Like find_xor_key, with the given scorer.
+pub fn find_xor_key_with<S: Scorer + ?Sized>(m: &[u8], scorer: &S) -> u8 {
+    rank_xor_keys_with(m, 1, scorer).first().map_or(0, |c| c.key)
+}
+
+// How far outside the usual run of scores a line's best score must be for detect_single_byte_xor
+// to call it likely. This is the cutoff on the modified z-score recommended by Iglewicz and
+// Hoaglin for flagging outliers; in challenge 4 the real line scores about 5.2 and the runner up
+// 2.7.
+const OUTLIER_Z: f32 = 3.5;
+
+// A line that may have been xored with a single byte: which line it was, the key and score of its
+// best candidate, the plaintext that gives, and how many robust standard deviations the score
+// lies below the median of all the lines' best scores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XorLineDetection {
+    pub line: usize,
+    pub key: u8,
+    pub score: f32,
+    pub plaintext: Vec<u8>,
+    pub significance: f32,
+}
+
+impl XorLineDetection {
+    // Whether the line stands out from the others enough to be taken as the xored one.
+    pub fn is_likely(&self) -> bool {
+        self.significance > OUTLIER_Z
+    }
+}
+
+fn median(sorted: &[f32]) -> f32 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+// Finds which of a set of lines of the same length, most of them noise, has been xored with a
+// single byte, as in challenge 4. Every non-empty line comes back with its best key, best
+// (lowest scoring) line first.
+//
+// Scores depend on the text, so rather than a fixed cutoff each line is compared with the rest:
+// its significance is the modified z-score of its best score, 0.6745 * (median - score) / MAD,
+// where MAD is the median absolute deviation of all the best scores. With fewer than three lines,
+// or lines that all score the same, nothing stands out and every significance is 0. Lines the
+// scorer gives NaN come last, take no part and have significance 0.
+pub fn detect_single_byte_xor<I>(lines: I) -> Vec<XorLineDetection>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]> + Sync
+{
+    use text::CharFreq;
+
+    detect_single_byte_xor_with(lines, &CharFreq::for_english())
+}
+
+// Like detect_single_byte_xor, with the given scorer.
+pub fn detect_single_byte_xor_with<I, S>(lines: I, scorer: &S) -> Vec<XorLineDetection>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]> + Sync,
+          S: Scorer + ?Sized
+{
+    let lines: Vec<I::Item> = lines.into_iter().collect();
+    let work = 256 * lines.iter().map(|line| line.as_ref().len()).sum::<usize>();
+    detect_single_byte_xor_on(&default_search(work), lines, scorer)
+}
+
+// Like detect_single_byte_xor_with, running on the given search, whose keys are the lines. If the
+// search is cancelled only the lines tried are compared.
+pub fn detect_single_byte_xor_on<I, S>(search: &Search, lines: I, scorer: &S)
+                                       -> Vec<XorLineDetection>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]> + Sync,
+          S: Scorer + ?Sized
+{
+    let lines: Vec<(usize, I::Item)> = lines.into_iter().enumerate().collect();
+    let found = search.run(&lines, |&(i, ref line)| {
+        let best = rank_xor_keys_on(&Search::sequential(), line.as_ref(), 1, scorer).pop();
+        Step::Continue(best.map(|c| {
+            XorLineDetection {
+                line: i,
+                key: c.key,
+                score: c.score,
+                plaintext: c.plaintext,
+                significance: 0.0,
+            }
+        }))
+    });
+    let mut detections: Vec<XorLineDetection> = found.into_iter().flatten().collect();
+    detections.sort_by(|a, b| by_score(a.score, b.score));
+
+    let scores: Vec<f32> = detections.iter().map(|d| d.score).filter(|s| !s.is_nan()).collect();
+    if scores.len() < 3 {
+        return detections;
+    }
+    let med = median(&scores);
+    let mut deviations: Vec<f32> = scores.iter().map(|s| (s - med).abs()).collect();
+    deviations.sort_by(|a, b| a.total_cmp(b));
+    let mad = median(&deviations);
+    if mad > 0.0 {
+        for d in detections.iter_mut().filter(|d| !d.score.is_nan()) {
+            d.significance = 0.6745 * (med - d.score) / mad;
+        }
+    }
+    detections
+}
+
+pub fn find_repeated_xor_key(m: &[u8], key_size: usize) -> Vec<u8> {
+    use text::CharFreq;
+
+    find_repeated_xor_key_with(m, key_size, &CharFreq::for_english())
+}
+
+// Like find_repeated_xor_key, with the given scorer.
+pub fn find_repeated_xor_key_with<S: Scorer + ?Sized>(m: &[u8], key_size: usize, scorer: &S)
+                                                      -> Vec<u8> {
+    find_repeated_xor_key_on(&default_search(256 * m.len()), m, key_size, scorer)
+}
+
+// Like find_repeated_xor_key_with, running on the given search, whose keys are the positions in
+// the key. If the search is cancelled the key stops short at the positions found.
+pub fn find_repeated_xor_key_on<S>(search: &Search, m: &[u8], key_size: usize, scorer: &S)
+                                   -> Vec<u8>
+    where S: Scorer + ?Sized
+{
+    // rotate the message into blocks separated by keysize.
+    let mut blocks: Vec<Vec<u8>> = vec!();
+    let chunks = m.chunks(key_size);
+    for chunk in chunks {
+        for (i, b) in chunk.iter().enumerate() {
+            if blocks.len() <= i {
+                blocks.push(vec!(*b));
+            } else if let Some(block) = blocks.get_mut(i) {
+                block.push(*b)
+            }
+        }
+    }
+    search.run(&blocks, |v| {
+        let best = rank_xor_keys_on(&Search::sequential(), v, 1, scorer);
+        Step::Continue(best.first().map_or(0, |c| c.key))
+    })
+}
+
+// A repeating xor key found for one key size, the plaintext it gives and that plaintext's score,
+// lower being better.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyGuess {
+    // The key size tried. The key can be shorter, if the one found repeats within it.
+    pub size: usize,
+    pub key: Vec<u8>,
+    pub score: f32,
+    pub plaintext: Vec<u8>,
+}
+
+// The outcome of break_repeating_xor: the best key and the plaintext it gives, with the keys found
+// for all the key sizes that were tried, best first, so alternatives[0] is the best guess itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakResult {
+    pub key: Vec<u8>,
+    pub plaintext: Vec<u8>,
+    pub score: f32,
+    pub alternatives: Vec<KeyGuess>,
+}
+
+// Shortens a key that is a shorter key repeated, as the key found for a multiple of the real key
+// size is, to that shorter key.
+fn shortest_period(key: &[u8]) -> &[u8] {
+    (1..key.len())
+        .filter(|p| key.len().is_multiple_of(*p))
+        .find(|&p| key.chunks(p).all(|c| c == &key[..p]))
+        .map_or(key, |p| &key[..p])
+}
+
+// Breaks repeating key xor, as in challenge 6: guesses the likely key sizes, finds the best key of
+// each size and keeps whichever gives the most English plaintext. Keys are reported at their
+// shortest, so a multiple of the real size finds the real key, and the alternatives say which
+// size each came from. An empty ciphertext gives an empty key and no alternatives.
+pub fn break_repeating_xor(m: &[u8]) -> BreakResult {
+    use text::CharFreq;
+
+    break_repeating_xor_with(m, &CharFreq::for_english())
+}
+
+// Like break_repeating_xor, with the given scorer.
+pub fn break_repeating_xor_with<S: Scorer + ?Sized>(m: &[u8], scorer: &S) -> BreakResult {
+    let sizes = guess_key_size(m);
+    break_repeating_xor_sizes(&default_search(256 * sizes.len() * m.len()), m, &sizes, scorer)
+}
+
+// Like break_repeating_xor_with, running on the given search, whose keys are the key sizes
+// guessed, tried one to a worker. Guessing the sizes takes a fraction of the time and isn't part
+// of the search. If the search is cancelled the alternatives are the sizes tried.
+pub fn break_repeating_xor_on<S>(search: &Search, m: &[u8], scorer: &S) -> BreakResult
+    where S: Scorer + ?Sized
+{
+    break_repeating_xor_sizes(search, m, &guess_key_size(m), scorer)
+}
+
+fn break_repeating_xor_sizes<S>(search: &Search, m: &[u8], sizes: &[usize], scorer: &S)
+                                -> BreakResult
+    where S: Scorer + ?Sized
+{
+    use combine::xor_each;
+    use std::f32;
+
+    let mut alternatives = search.run(sizes, |&size| {
+        let key = find_repeated_xor_key_on(&Search::sequential(), m, size, scorer);
+        let key = shortest_period(&key).to_vec();
+        let plaintext = xor_each(m, &key);
+        Step::Continue(KeyGuess {
+            size,
+            score: scorer.score(&plaintext),
+            key,
+            plaintext,
+        })
+    });
+    alternatives.sort_by(|a, b| by_score(a.score, b.score));
+    alternatives.dedup_by(|a, b| (a.size, &a.key) == (b.size, &b.key));
+
+    let (key, plaintext, score) = match alternatives.first() {
+        Some(best) => (best.key.clone(), best.plaintext.clone(), best.score),
+        None => (vec![], vec![], f32::MAX),
+    };
+    BreakResult { key, plaintext, score, alternatives }
+}
+
+// Ways of estimating the size of a repeating xor key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySizeMethod {
+    // The average Hamming distance between every pair of key sized blocks, per byte. Blocks
+    // encrypted with the same key differ as much as their plaintexts do, which for text is less
+    // than random bytes differ. Lower is better.
+    Hamming,
+    // Friedman's test: the average index of coincidence of the bytes at each position in the key.
+    // Each of those is single byte xor of text, so repeats as often as text does, while bytes
+    // xored with different key bytes are closer to random. Higher is better.
+    Coincidence,
+    // The fraction of bytes equal to the byte the key size further on, which were xored with the
+    // same key byte when the size is right. Higher is better.
+    Autocorrelation,
+}
+
+// A possible key size and the score the estimating method gave it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeySize {
+    pub size: usize,
+    pub score: f32,
+}
+
+// The most blocks compared by KeySizeMethod::Hamming, to keep the number of pairs, and so the
+// time taken, bounded on long ciphertexts.
+const MAX_HAMMING_BLOCKS: usize = 64;
+
+fn average_hamming(m: &[u8], size: usize) -> Option<f32> {
+    use measure::hamming;
+
+    let blocks: Vec<&[u8]> = m.chunks_exact(size).take(MAX_HAMMING_BLOCKS).collect();
+    if blocks.len() < 2 {
+        return None;
+    }
+    let mut total = 0;
+    let mut pairs = 0;
+    for (i, a) in blocks.iter().enumerate() {
+        for b in &blocks[i + 1..] {
+            total += hamming(a, b);
+            pairs += 1;
+        }
+    }
+    Some(total as f32 / pairs as f32 / size as f32)
+}
+
+fn average_coincidence(m: &[u8], size: usize) -> Option<f32> {
+    // Every column needs two bytes to have a chance of a coincidence.
+    if m.len() < size * 2 {
+        return None;
+    }
+    let mut total = 0.0;
+    for column in 0..size {
+        let mut counts = [0usize; 256];
+        let mut n = 0;
+        for &b in m[column..].iter().step_by(size) {
+            counts[b as usize] += 1;
+            n += 1;
+        }
+        let same: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+        total += same as f32 / (n * (n - 1)) as f32;
+    }
+    Some(total / size as f32)
+}
+
+fn autocorrelation(m: &[u8], size: usize) -> Option<f32> {
+    if m.len() <= size {
+        return None;
+    }
+    let same = m.iter().zip(&m[size..]).filter(|&(a, b)| a == b).count();
+    Some(same as f32 / (m.len() - size) as f32)
+}
+
+// Scores each key size in sizes with the given method and returns them most likely first. Sizes
+// that m is too short to say anything about, such as those with fewer than two whole blocks for
+// Hamming, are left out, so a short ciphertext may give few sizes or none.
+pub fn rank_key_sizes(m: &[u8], sizes: Range<usize>, method: KeySizeMethod) -> Vec<KeySize> {
+    let work = sizes.len() * m.len();
+    rank_key_sizes_on(&default_search(work), m, sizes, method)
+}
+
+// Like rank_key_sizes, running on the given search, whose keys are the sizes. If the search is
+// cancelled only the sizes tried are ranked.
+pub fn rank_key_sizes_on(search: &Search, m: &[u8], sizes: Range<usize>, method: KeySizeMethod)
+                         -> Vec<KeySize> {
+    let sizes: Vec<usize> = sizes.filter(|&size| size > 0).collect();
+    let scored = search.run(&sizes, |&size| {
+        let score = match method {
+            KeySizeMethod::Hamming => average_hamming(m, size),
+            KeySizeMethod::Coincidence => average_coincidence(m, size),
+            KeySizeMethod::Autocorrelation => autocorrelation(m, size),
+        };
+        Step::Continue(score.map(|score| KeySize { size, score }))
+    });
+    let mut ranked: Vec<KeySize> = scored.into_iter().flatten().collect();
+    ranked.sort_by(|a, b| {
+        match method {
+            KeySizeMethod::Hamming => by_score(a.score, b.score),
+            _ => by_score(-a.score, -b.score),
+        }
+    });
+    ranked
+}
+
+impl KeySizeMethod {
+    // The score expected of a wrong key size, where bytes are no more alike than random ones.
+    fn random_score(&self) -> f32 {
+        match *self {
+            KeySizeMethod::Hamming => 4.0,
+            KeySizeMethod::Coincidence | KeySizeMethod::Autocorrelation => 1.0 / 256.0,
+        }
+    }
+}
+
+// The number of sizes guess_key_size takes from each method.
+const GUESSES_PER_METHOD: usize = 3;
+
+// How much of a size's signal, its score's distance from random, a factor of that size needs for
+// guess_key_size to take the factor instead.
+const FACTOR_SIGNAL: f32 = 0.9;
+
+// Guesses the likely sizes of the repeating xor key m was encrypted with, between 2 and 40 bytes,
+// most likely first. Every multiple of the real size scores about as well as the size itself, so
+// each of the best few sizes by each method is replaced by its smallest factor that scores nearly
+// as well. The methods go wrong in different ways, Hamming being noisy on short inputs, so leaving
+// checking which guess is right to the caller is better than trusting any one of them.
+pub fn guess_key_size(m: &[u8]) -> Vec<usize> {
+    let mut guesses = vec![];
+    for &method in [KeySizeMethod::Hamming,
+                    KeySizeMethod::Coincidence,
+                    KeySizeMethod::Autocorrelation]
+                       .iter() {
+        let ranked = rank_key_sizes(m, 2..41, method);
+        let signal = |size| {
+            ranked.iter()
+                  .find(|k| k.size == size)
+                  .map_or(0.0, |k| (k.score - method.random_score()).abs())
+        };
+        for k in ranked.iter().take(GUESSES_PER_METHOD) {
+            let size = (2..k.size)
+                           .filter(|f| k.size % f == 0)
+                           .find(|&f| signal(f) >= FACTOR_SIGNAL * signal(k.size))
+                           .unwrap_or(k.size);
+            if !guesses.contains(&size) {
+                guesses.push(size);
+            }
+        }
+    }
+    guesses
+}
+
+#[test]
+fn test_rank_xor_keys() {
+    use combine::xor_byte;
+
+    let cipher = xor_byte(b"Cooking MC's like a pound of bacon", 88);
+    let ranked = rank_xor_keys(&cipher, 5);
+    assert_eq!(ranked.len(), 5);
+    assert_eq!(ranked[0].key, 88);
+    assert_eq!(ranked[0].plaintext, b"Cooking MC's like a pound of bacon".to_vec());
+    assert!(ranked.windows(2).all(|w| w[0].score <= w[1].score));
+    assert_eq!(find_xor_key(&cipher), 88);
+
+    assert_eq!(rank_xor_keys(&cipher, 0), vec![]);
+    assert_eq!(rank_xor_keys(&cipher, 1000).len(), 256);
+    assert_eq!(rank_xor_keys(&[], 3), vec![]);
+}
+
+#[test]
+fn test_rank_xor_keys_with_nan_scores() {
+    use combine::xor_byte;
+
+    // Makes sense of one plaintext only.
+    struct Unsure;
+    impl Scorer for Unsure {
+        fn score(&self, m: &[u8]) -> f32 {
+            if m == b"bacon" {
+                1.0
+            } else {
+                -f32::NAN
+            }
+        }
+    }
+    let ranked = rank_xor_keys_with(&xor_byte(b"bacon", 7), 256, &Unsure);
+    assert_eq!((ranked[0].key, ranked[0].score), (7, 1.0));
+    assert!(ranked[1..].iter().all(|c| c.score.is_nan()));
+}
+
+#[test]
+fn test_rank_xor_keys_on() {
+    use combine::xor_byte;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use text::CharFreq;
+
+    let cipher = xor_byte(b"Cooking MC's like a pound of bacon", 88);
+    let calls = AtomicUsize::new(0);
+    let search = Search::new().threads(2).on_progress(|_, total| {
+        assert_eq!(total, 256);
+        calls.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(rank_xor_keys_on(&search, &cipher, 1, &CharFreq::for_english())[0].key, 88);
+    assert_eq!(calls.load(Ordering::Relaxed), 256);
+
+    // Stopped before the right key is tried.
+    let cancel = AtomicBool::new(false);
+    let search = Search::sequential()
+        .on_progress(|done, _| if done == 10 { cancel.store(true, Ordering::Relaxed) })
+        .cancel_on(&cancel);
+    let ranked = rank_xor_keys_on(&search, &cipher, 256, &CharFreq::for_english());
+    assert_eq!(ranked.len(), 10);
+    assert!(ranked.iter().all(|c| c.key < 10));
+}
+
+#[test]
+fn test_find_xor_key_with() {
+    use combine::xor_byte;
+    use text::{Bhattacharyya, ByteFreq, ChiSquared, LogLikelihood};
+
+    let plain = b"Cooking MC's like a pound of bacon";
+    let cipher = xor_byte(plain, 88);
+    let scorers: Vec<Box<dyn Scorer>> = vec![Box::new(ChiSquared(ByteFreq::for_english())),
+                                             Box::new(Bhattacharyya(ByteFreq::for_english())),
+                                             Box::new(LogLikelihood(ByteFreq::for_english()))];
+    for scorer in scorers.iter() {
+        assert_eq!(find_xor_key_with(&cipher, &**scorer), 88);
+        let ranked = rank_xor_keys_with(&cipher, 2, &**scorer);
+        assert_eq!(ranked[0].plaintext, plain.to_vec());
+        // Flipping the case of every letter is the nearest miss, and one these scorers can see.
+        assert!(ranked[0].score < ranked[1].score);
+    }
+}
+
+#[test]
+fn test_find_xor_key_ff_and_latin1() {
+    use combine::xor_byte;
+
+    // Latin-1, which isn't valid UTF-8, with a stray NUL, xored with the key that used to be
+    // skipped.
+    let plain = b"Un caf\xe9 cr\xe8me br\xfbl\xe9e, s'il vous pla\xeet, gar\xe7on\x00";
+    let cipher = xor_byte(plain, 0xff);
+    let ranked = rank_xor_keys(&cipher, 1);
+    assert_eq!(ranked[0].key, 0xff);
+    assert_eq!(ranked[0].plaintext, plain.to_vec());
+
+    let plain = b"Pure ASCII, but the key has every bit set";
+    assert_eq!(find_xor_key(&xor_byte(plain, 0xff)), 0xff);
+}
+
+#[test]
+fn test_detect_single_byte_xor() {
+    use combine::xor_byte;
+
+    // Noise lines, one of which is English under a single byte key.
+    let mut lines: Vec<Vec<u8>> = (0..40u32)
+        .map(|i| (0..40u32).map(|j| ((i * 131 + j * 29) ^ (j * j * 7)) as u8).collect())
+        .collect();
+    lines[17] = xor_byte(b"Now that the party is jumping, go home!\n", 0x35);
+    let detections = detect_single_byte_xor(&lines);
+    assert_eq!(detections.len(), 40);
+    assert_eq!((detections[0].line, detections[0].key), (17, 0x35));
+    assert!(detections[0].is_likely());
+    assert!(detections[1..].iter().all(|d| !d.is_likely()));
+
+    // Too few lines to tell anything apart.
+    let detections = detect_single_byte_xor(&lines[16..18]);
+    assert_eq!(detections[0].line, 1);
+    assert!(detections.iter().all(|d| d.significance == 0.0));
+    assert_eq!(detect_single_byte_xor(Vec::<Vec<u8>>::new()), vec![]);
+
+    // A scorer that makes nothing of any line finds nothing, rather than panicking.
+    struct Clueless;
+    impl Scorer for Clueless {
+        fn score(&self, _: &[u8]) -> f32 {
+            f32::NAN
+        }
+    }
+    let detections = detect_single_byte_xor_with(&lines, &Clueless);
+    assert!(detections.iter().all(|d| d.score.is_nan() && d.significance == 0.0));
+}
+
+#[cfg(test)]
+const ALICE: &str = "Alice was beginning to get very tired of sitting by her sister on the bank, and
+    of having nothing to do: once or twice she had peeped into the book her sister was reading,
+    but it had no pictures or conversations in it, `and what is the use of a book,' thought
+    Alice `without pictures or conversation?' So she was considering in her own mind (as well
+    as she could, for the hot day made her feel very sleepy and stupid), whether the pleasure
+    of making a daisy-chain would be worth the trouble of getting up and picking the daisies,
+    when suddenly a White Rabbit with pink eyes ran close by her.  There was nothing so very
+    remarkable in that; nor did Alice think it so very much out of the way to hear the Rabbit
+    say to itself, `Oh dear! Oh dear! I shall be late!' (when she thought it over afterwards,
+    it occurred to her that she ought to have wondered at this, but at the time it all seemed
+    quite natural); but when the Rabbit actually took a watch out of its waistcoat-pocket, and
+    looked at it, and then hurried on, Alice started to her feet, for it flashed across her
+    mind that she had never before seen a rabbit with either a waistcoat-pocket, or a watch to
+    take out of it, and burning with curiosity, she ran across the field after it, and
+    fortunately was just in time to see it pop down a large rabbit-hole under the hedge.";
+
+#[test]
+fn test_break_repeating_xor() {
+    use combine::xor_each;
+    use text::NgramModel;
+
+    let plain = ALICE.as_bytes();
+    let result = break_repeating_xor(&xor_each(plain, b"ICE"));
+    assert_eq!(result.key, b"ICE".to_vec());
+    assert_eq!(result.plaintext, plain.to_vec());
+    assert_eq!(result.alternatives[0].key, result.key);
+    assert!(result.alternatives.windows(2).all(|w| w[0].score <= w[1].score));
+    assert!(result.alternatives.iter().all(|g| g.size % g.key.len() == 0));
+
+    let model = NgramModel::from_corpus(3, include_str!("../data/english.txt").as_bytes());
+    let result = break_repeating_xor_with(&xor_each(plain, b"ICE"), &model);
+    assert_eq!(result.key, b"ICE".to_vec());
+
+    assert_eq!(shortest_period(b"abcabcabc"), b"abc");
+    assert_eq!(shortest_period(b"abcabcab"), b"abcabcab");
+    assert_eq!(shortest_period(b""), b"");
+
+    let result = break_repeating_xor(&[]);
+    assert_eq!((result.key, result.plaintext, result.alternatives), (vec![], vec![], vec![]));
+}
+
+#[test]
+fn test_guess_key_size() {
+    use combine::xor_each;
+
+    let msg = ALICE.as_bytes();
+
+    // Multiples of the key size are as good as the size itself, but shouldn't crowd it out.
+    for key in [&b"ICE"[..], b"YELLOW SUBMARINE!"].iter() {
+        let cipher = xor_each(msg, key);
+        assert_eq!(guess_key_size(&cipher)[0], key.len());
+    }
+
+    let key = [0x11, 0x23, 0x3f, 0xf9, 0x82, 0x12, 0x99, 0x22];
+    let cipher = xor_each(msg, &key);
+    assert!(guess_key_size(&cipher).contains(&key.len()));
+
+    let key = [0x1, 0x99];
+    let cipher = xor_each(msg, &key);
+    assert!(guess_key_size(&cipher).contains(&key.len()));
+
+    let key = [0x1, 0x99, 0xf2, 0x90, 0x01, 0x65, 0x69, 0x82, 0x11, 0x20, 0x30];
+    let cipher = xor_each(msg, &key);
+    assert!(guess_key_size(&cipher).contains(&key.len()));
+}
+
+#[test]
+fn test_rank_key_sizes() {
+    use combine::xor_each;
+
+    let key = b"YELLOW SUBMARINE!";
+    let cipher = xor_each(ALICE.as_bytes(), key);
+    for &method in [KeySizeMethod::Hamming,
+                    KeySizeMethod::Coincidence,
+                    KeySizeMethod::Autocorrelation]
+                       .iter() {
+        let ranked = rank_key_sizes(&cipher, 2..41, method);
+        assert_eq!(ranked.len(), 39);
+        assert_eq!(ranked[0].size % key.len(), 0, "{:?} ranked {:?}", method, ranked[0]);
+    }
+    let ranked = rank_key_sizes(&cipher, 2..41, KeySizeMethod::Hamming);
+    assert!(ranked.windows(2).all(|w| w[0].score <= w[1].score));
+    let ranked = rank_key_sizes(&cipher, 5..6, KeySizeMethod::Coincidence);
+    assert_eq!(ranked.iter().map(|k| k.size).collect::<Vec<_>>(), vec![5]);
+}
+
+#[test]
+fn test_key_sizes_of_short_ciphertexts() {
+    // Sizes without enough data are left out rather than scored as NaN.
+    let m = b"0123456789";
+    let sizes = |method| -> Vec<usize> {
+        rank_key_sizes(m, 0..20, method).iter().map(|k| k.size).collect()
+    };
+    assert_eq!(sizes(KeySizeMethod::Hamming).len(), 5);
+    assert!(sizes(KeySizeMethod::Hamming).iter().all(|&s| (1..=5).contains(&s)));
+    assert!(sizes(KeySizeMethod::Coincidence).iter().all(|&s| (1..=5).contains(&s)));
+    assert!(sizes(KeySizeMethod::Autocorrelation).iter().all(|&s| (1..=9).contains(&s)));
+    assert_eq!(guess_key_size(b""), vec![]);
+    assert_eq!(guess_key_size(b"ab"), vec![]);
+    assert!(!guess_key_size(b"abcd").is_empty());
+}
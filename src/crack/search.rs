@@ -0,0 +1,216 @@
+// A brute force engine: tries a function on every key in a keyspace, spreading the keys across
+// worker threads. The crack functions are all built on it, and their _on variants take a search
+// to run on, so progress can be reported and a long crack stopped from outside. It can also be
+// used directly for searches of their own, which can stop early once the answer has been found.
+
+use std::cmp;
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+// What trying a key came to: a result to keep, and whether to carry on trying the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<T> {
+    Continue(T),
+    // Keep this result and start no more keys. Keys other threads have already started still
+    // finish, and their results are kept too.
+    Stop(T),
+}
+
+// How a search is run: on how many threads, who to tell about progress, and what can stop it.
+pub struct Search<'a> {
+    threads: usize,
+    progress: Option<Box<dyn Fn(usize, usize) + Sync + 'a>>,
+    cancel: Option<&'a AtomicBool>,
+}
+
+impl<'a> Search<'a> {
+    // A search on as many threads as the machine has cores.
+    pub fn new() -> Search<'a> {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Search { threads, progress: None, cancel: None }
+    }
+
+    // A search on the calling thread alone, for searches run inside another search's workers.
+    pub fn sequential() -> Search<'a> {
+        Search { threads: 1, progress: None, cancel: None }
+    }
+
+    // Sets the number of worker threads. Panics if threads is 0.
+    pub fn threads(mut self, threads: usize) -> Search<'a> {
+        assert!(threads > 0, "a search needs at least one thread");
+        self.threads = threads;
+        self
+    }
+
+    // Calls progress after each key is tried with the number tried so far and the number there
+    // are in all. It is called from the worker threads, so calls can arrive out of order.
+    pub fn on_progress<F>(mut self, progress: F) -> Search<'a>
+        where F: Fn(usize, usize) + Sync + 'a
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    // Stops the search once cancel is set, from a progress callback or another thread, as if a
+    // key had returned Step::Stop: keys already started finish and no more are tried.
+    pub fn cancel_on(mut self, cancel: &'a AtomicBool) -> Search<'a> {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    // Tries every key with try_key and returns the results in the order of the keys. If try_key
+    // stops the search, or it is cancelled, some keys won't have been tried, and have no result.
+    // A panic in try_key is passed on once the other workers have finished.
+    pub fn run<K, T, F>(&self, keys: &[K], try_key: F) -> Vec<T>
+        where K: Sync,
+              T: Send,
+              F: Fn(&K) -> Step<T> + Sync
+    {
+        let next = AtomicUsize::new(0);
+        let tried = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let work = || {
+            let mut found = vec![];
+            while !stop.load(Ordering::Relaxed) && !self.cancelled() {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= keys.len() {
+                    break;
+                }
+                match try_key(&keys[i]) {
+                    Step::Continue(result) => found.push((i, result)),
+                    Step::Stop(result) => {
+                        found.push((i, result));
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+                let n = tried.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref progress) = self.progress {
+                    progress(n, keys.len());
+                }
+            }
+            found
+        };
+
+        let threads = cmp::min(self.threads, keys.len());
+        let mut found = if threads <= 1 {
+            work()
+        } else {
+            thread::scope(|s| {
+                let workers: Vec<_> = (0..threads).map(|_| s.spawn(work)).collect();
+                let mut found = vec![];
+                for worker in workers {
+                    found.extend(worker.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+                }
+                found
+            })
+        };
+        found.sort_by_key(|&(i, _)| i);
+        found.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl<'a> Default for Search<'a> {
+    fn default() -> Search<'a> {
+        Search::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_run_in_order() {
+        let keys: Vec<u32> = (0..1000).collect();
+        for threads in 1..9 {
+            let squares = Search::new().threads(threads).run(&keys, |&k| Step::Continue(k * k));
+            assert_eq!(squares, keys.iter().map(|k| k * k).collect::<Vec<_>>());
+        }
+        assert_eq!(Search::new().run(&[] as &[u32], |&k| Step::Continue(k)), vec![]);
+    }
+
+    #[test]
+    fn test_stop_early() {
+        let keys: Vec<u32> = (0..100000).collect();
+        let stop_at_500 = |tried: &AtomicUsize, k: u32| {
+            tried.fetch_add(1, Ordering::Relaxed);
+            if k == 500 {
+                Step::Stop(k)
+            } else {
+                Step::Continue(k)
+            }
+        };
+
+        let tried = AtomicUsize::new(0);
+        let found = Search::sequential().run(&keys, |&k| stop_at_500(&tried, k));
+        assert_eq!(found, (0..501).collect::<Vec<_>>());
+        assert_eq!(tried.load(Ordering::Relaxed), 501);
+
+        // How many keys the other workers get through first depends on how they're scheduled.
+        let tried = AtomicUsize::new(0);
+        let found = Search::new().threads(4).run(&keys, |&k| stop_at_500(&tried, k));
+        assert!(found.contains(&500));
+        assert_eq!(found.len(), tried.load(Ordering::Relaxed));
+        assert!(found.len() < keys.len());
+    }
+
+    #[test]
+    fn test_progress() {
+        let keys: Vec<u32> = (0..200).collect();
+        let seen = Mutex::new(vec![]);
+        Search::new()
+            .threads(3)
+            .on_progress(|done, total| seen.lock().unwrap().push((done, total)))
+            .run(&keys, |&k| Step::Continue(k));
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(seen, (1..201).map(|n| (n, 200)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let keys: Vec<u32> = (0..100000).collect();
+        let cancel_at_1000 = |cancel: &AtomicBool, done: usize| {
+            if done == 1000 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+
+        let cancel = AtomicBool::new(false);
+        let found = Search::sequential()
+                        .on_progress(|done, _| cancel_at_1000(&cancel, done))
+                        .cancel_on(&cancel)
+                        .run(&keys, |&k| Step::Continue(k));
+        assert_eq!(found, keys[..1000].to_vec());
+
+        // Already cancelled, so nothing is tried.
+        let found = Search::sequential().cancel_on(&cancel).run(&keys, |&k| Step::Continue(k));
+        assert_eq!(found, vec![]);
+
+        // Keys are handed out in order, so the workers still return a run from the start.
+        let cancel = AtomicBool::new(false);
+        let found = Search::new()
+                        .threads(4)
+                        .on_progress(|done, _| cancel_at_1000(&cancel, done))
+                        .cancel_on(&cancel)
+                        .run(&keys, |&k| Step::Continue(k));
+        assert!(found.len() >= 1000 && found.len() < keys.len());
+        assert_eq!(found, keys[..found.len()].to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "key 7")]
+    fn test_panics_are_passed_on() {
+        let keys: Vec<u32> = (0..20).collect();
+        Search::new().threads(4).run(&keys, |&k| {
+            assert!(k != 7, "key 7");
+            Step::Continue(k)
+        });
+    }
+}
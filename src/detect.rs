@@ -0,0 +1,169 @@
+// Spotting block ciphers in ECB mode. ECB encrypts equal plaintext blocks to equal ciphertext
+// blocks, while the blocks of a good cipher's output otherwise essentially never repeat, so
+// repeated blocks give ECB away whichever cipher is underneath: DES and Blowfish with 8 byte
+// blocks as much as AES with 16.
+
+use measure::repeated_blocks;
+
+// The block sizes checked: DES and Blowfish, AES, and 256 bit blocks as in Rijndael-256.
+pub const BLOCK_SIZES: [usize; 3] = [8, 16, 32];
+
+// The block size reported for ciphertexts that give no hint of their own, AES's.
+const DEFAULT_BLOCK_SIZE: usize = 16;
+
+// How ECB like a ciphertext looks at one block size: the share of its whole blocks that repeat an
+// earlier block, from 0 when none do to nearly 1 when they are all the same. Panics if block_size
+// is 0.
+pub fn ecb_score(ciphertext: &[u8], block_size: usize) -> f64 {
+    let blocks = ciphertext.len() / block_size;
+    if blocks == 0 {
+        return 0.0;
+    }
+    repeated_blocks(ciphertext, block_size) as f64 / blocks as f64
+}
+
+// Works out the block size of an ECB ciphertext from its repeats, or None if no block repeats at
+// any size in BLOCK_SIZES that divides its length.
+//
+// A repeated block also repeats as halves, covering the same bytes, so this starts at the smallest
+// size with repeats and steps up while the repeats at the next size cover as many bytes. Equal
+// plaintext blocks that only ever come in pairs aligned to twice the block size can't be told
+// from a cipher with blocks twice as big, and are reported as the bigger size.
+pub fn infer_block_size(ciphertext: &[u8]) -> Option<usize> {
+    let mut found = None;
+    for &size in BLOCK_SIZES.iter().filter(|&&size| ciphertext.len().is_multiple_of(size)) {
+        let covered = size * repeated_blocks(ciphertext, size);
+        match found {
+            None if covered > 0 => found = Some((size, covered)),
+            Some((_, before)) if covered == before => found = Some((size, covered)),
+            Some(_) => break,
+            None => {}
+        }
+    }
+    found.map(|(size, _)| size)
+}
+
+// How one ciphertext among many scored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EcbCandidate {
+    // Its position among the ciphertexts ranked.
+    pub index: usize,
+    pub block_size: usize,
+    // The number of blocks repeating an earlier one.
+    pub repeats: usize,
+    pub score: f64,
+}
+
+// Scores every ciphertext at block_size and ranks them, most ECB like first and in their original
+// order on ties. Panics if block_size is 0.
+pub fn rank_ecb<I>(ciphertexts: I, block_size: usize) -> Vec<EcbCandidate>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]>
+{
+    rank_ecb_by(ciphertexts, |_| block_size)
+}
+
+// Like rank_ecb, but scores each ciphertext at the block size inferred from its own repeats, for
+// captures that could be from more than one cipher. Those without repeats are scored at 16 bytes.
+pub fn rank_ecb_inferred<I>(ciphertexts: I) -> Vec<EcbCandidate>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]>
+{
+    rank_ecb_by(ciphertexts,
+                |ciphertext| infer_block_size(ciphertext).unwrap_or(DEFAULT_BLOCK_SIZE))
+}
+
+fn rank_ecb_by<I, F>(ciphertexts: I, block_size_of: F) -> Vec<EcbCandidate>
+    where I: IntoIterator,
+          I::Item: AsRef<[u8]>,
+          F: Fn(&[u8]) -> usize
+{
+    let score = |(index, ciphertext): (usize, I::Item)| {
+        let ciphertext = ciphertext.as_ref();
+        let size = block_size_of(ciphertext);
+        EcbCandidate {
+            index,
+            block_size: size,
+            repeats: repeated_blocks(ciphertext, size),
+            score: ecb_score(ciphertext, size),
+        }
+    };
+    let mut ranked: Vec<EcbCandidate> = ciphertexts.into_iter().enumerate().map(score).collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use measure::pseudo_random;
+
+    // A stand in for a block cipher in ECB mode: each block is replaced by bytes generated from
+    // its hash, so equal blocks encrypt to equal blocks and the rest look random.
+    fn fake_ecb(plaintext: &[u8], block_size: usize) -> Vec<u8> {
+        plaintext.chunks(block_size)
+                 .flat_map(|block| {
+                     let mut x = block.iter().fold(0xcbf29ce484222325u64, |h, &b| {
+                         (h ^ b as u64).wrapping_mul(0x100000001b3)
+                     });
+                     (0..block_size).map(move |_| {
+                         x ^= x << 13;
+                         x ^= x >> 7;
+                         x ^= x << 17;
+                         (x >> 24) as u8
+                     })
+                 })
+                 .collect()
+    }
+
+    // 24 blocks of noise, three of which repeat earlier ones.
+    fn plaintext(block_size: usize) -> Vec<u8> {
+        let mut blocks: Vec<Vec<u8>> =
+            (0..24).map(|i| pseudo_random(block_size, i + 1)).collect();
+        blocks[5] = blocks[1].clone();
+        blocks[9] = blocks[1].clone();
+        blocks[14] = blocks[3].clone();
+        blocks.concat()
+    }
+
+    #[test]
+    fn test_ecb_score() {
+        let ciphertext = fake_ecb(&plaintext(16), 16);
+        assert_eq!(ecb_score(&ciphertext, 16), 3.0 / 24.0);
+        assert_eq!(ecb_score(&ciphertext, 32), 0.0);
+        assert_eq!(ecb_score(&pseudo_random(384, 7), 16), 0.0);
+        assert_eq!(ecb_score(b"", 16), 0.0);
+        assert_eq!(ecb_score(b"short", 8), 0.0);
+    }
+
+    #[test]
+    fn test_infer_block_size() {
+        for &size in BLOCK_SIZES.iter() {
+            assert_eq!(infer_block_size(&fake_ecb(&plaintext(size), size)), Some(size));
+        }
+        assert_eq!(infer_block_size(&pseudo_random(384, 7)), None);
+        assert_eq!(infer_block_size(b""), None);
+        // Repeats at a size that doesn't divide the length don't count.
+        assert_eq!(infer_block_size(&[0; 20]), None);
+        assert_eq!(infer_block_size(&[0; 24]), Some(8));
+    }
+
+    #[test]
+    fn test_rank_ecb() {
+        let mut ciphertexts: Vec<Vec<u8>> =
+            (0..10).map(|i| pseudo_random(192, i + 100)).collect();
+        ciphertexts[6] = fake_ecb(&plaintext(8), 8);
+        let ranked = rank_ecb(&ciphertexts, 8);
+        assert_eq!(ranked.len(), 10);
+        assert_eq!((ranked[0].index, ranked[0].repeats), (6, 3));
+        assert!(ranked[1..].iter().all(|c| c.score == 0.0));
+        // Ties keep their order.
+        assert_eq!(ranked[1].index, 0);
+
+        ciphertexts[2] = fake_ecb(&plaintext(32), 32);
+        let ranked = rank_ecb_inferred(&ciphertexts);
+        assert_eq!((ranked[0].index, ranked[0].block_size), (2, 32));
+        assert_eq!((ranked[1].index, ranked[1].block_size), (6, 8));
+        assert_eq!(ranked[2].block_size, 16);
+    }
+}
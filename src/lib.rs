@@ -1,9 +1,10 @@
-extern crate openssl;
-
+pub mod aes;
 pub mod conversions;
 pub mod combine;
 pub mod crack;
+pub mod detect;
 pub mod text;
 pub mod measure;
+pub mod triage;
 
 pub mod challenges;
@@ -1,5 +1,200 @@
+// Measurements of byte strings: how far apart two are, and statistics of a single one's bytes
+// that tell plaintext from compressed, encrypted or encoded data.
+
+use std::cmp;
+use std::collections::HashSet;
+use std::f64;
+
+// The number of bits that differ between a and b. If one is longer, every bit of its extra bytes
+// counts as different, as if the shorter one had run out of bits.
 pub fn hamming(a: &[u8], b: &[u8]) -> u32 {
-    a.iter().zip(b.iter()).fold(0, |acc, (a, b)| (a ^ b).count_ones() + acc)
+    let common = a.iter().zip(b.iter()).fold(0, |acc, (a, b)| (a ^ b).count_ones() + acc);
+    let extra = cmp::max(a.len(), b.len()) - cmp::min(a.len(), b.len());
+    common + 8 * extra as u32
+}
+
+// The number of single byte insertions, deletions and substitutions it takes to turn a into b.
+pub fn levenshtein(a: &[u8], b: &[u8]) -> usize {
+    // Distances from a prefix of a to every prefix of b, a row at a time.
+    let mut prev: Vec<usize> = (0..b.len() + 1).collect();
+    let mut row = vec![0; b.len() + 1];
+    for (i, &x) in a.iter().enumerate() {
+        row[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            let substitute = prev[j] + if x == y { 0 } else { 1 };
+            row[j + 1] = cmp::min(substitute, cmp::min(prev[j + 1], row[j]) + 1);
+        }
+        ::std::mem::swap(&mut prev, &mut row);
+    }
+    prev[b.len()]
+}
+
+// How many times each byte value occurs.
+pub fn histogram(bytes: &[u8]) -> [usize; 256] {
+    let mut counts = [0; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    counts
+}
+
+// Shannon entropy in bits per byte, from 0 for a single repeated byte to 8 for bytes that are all
+// equally common. Empty input has none.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let n = bytes.len() as f64;
+    // Folded from 0.0 rather than summed, as an empty sum is -0.0 and so is -1.0 * log2(1.0).
+    histogram(bytes)
+        .iter()
+        .filter(|&&c| c > 0)
+        .fold(0.0, |h, &c| {
+            let p = c as f64 / n;
+            h - p * p.log2()
+        })
+}
+
+// The chance that two bytes picked from different places are the same. Random bytes give about
+// 1/256, English text about 0.065. Fewer than two bytes give 0.
+pub fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    let n = bytes.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let same: usize = histogram(bytes).iter().map(|&c| c * c.saturating_sub(1)).sum();
+    same as f64 / (n * (n - 1)) as f64
+}
+
+// The result of testing whether bytes are uniformly distributed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uniformity {
+    // Pearson's chi-squared statistic against every byte value being equally likely, on 255
+    // degrees of freedom.
+    pub chi_squared: f64,
+    // The chance of a statistic at least this large if the bytes really are uniform. Encrypted
+    // and well compressed data should rarely fall below 0.01; text is always far below.
+    pub p_value: f64,
+}
+
+// Coefficients of the polynomial in erfc, lowest power first.
+const ERFC_COEFFICIENTS: [f64; 10] = [-1.26551223, 1.00002368, 0.37409196, 0.09678418,
+                                      -0.18628806, 0.27886807, -1.13520398, 1.48851587,
+                                      -0.82215223, 0.17087277];
+
+// The complementary error function, to within 1.2e-7 (Numerical Recipes' erfcc).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = ERFC_COEFFICIENTS.iter().rev().fold(0.0, |acc, &c| acc * t + c);
+    let r = t * (poly - z * z).exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+// Chi-squared test of bytes against the uniform distribution. The p-value uses the Wilson-Hilferty
+// normal approximation, which is good to a few decimal places at 255 degrees of freedom. Empty
+// input can't be told apart from anything, and gets a p-value of 1.
+pub fn chi_squared_uniformity(bytes: &[u8]) -> Uniformity {
+    if bytes.is_empty() {
+        return Uniformity { chi_squared: 0.0, p_value: 1.0 };
+    }
+    let expected = bytes.len() as f64 / 256.0;
+    let chi_squared: f64 = histogram(bytes)
+        .iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum();
+    let k = 255.0;
+    let spread = 2.0 / (9.0 * k);
+    let z = ((chi_squared / k).powf(1.0 / 3.0) - (1.0 - spread)) / spread.sqrt();
+    Uniformity { chi_squared, p_value: 0.5 * erfc(z / f64::consts::SQRT_2) }
+}
+
+// The correlation between each byte and the next, wrapping around from the last to the first,
+// as the ent tool measures it: close to 0 for random data, and further from it the more each byte
+// depends on the one before. None if there are fewer than two bytes or they are all the same.
+pub fn serial_correlation(bytes: &[u8]) -> Option<f64> {
+    let n = bytes.len() as f64;
+    if bytes.len() < 2 {
+        return None;
+    }
+    let next = bytes.iter().skip(1).chain(bytes.iter().take(1));
+    let (mut sum, mut sum_sq, mut sum_next) = (0.0, 0.0, 0.0);
+    for (&x, &y) in bytes.iter().zip(next) {
+        let (x, y) = (x as f64, y as f64);
+        sum += x;
+        sum_sq += x * x;
+        sum_next += x * y;
+    }
+    let denominator = n * sum_sq - sum * sum;
+    if denominator == 0.0 {
+        return None;
+    }
+    Some((n * sum_next - sum * sum) / denominator)
+}
+
+// The number of block_size byte blocks that repeat an earlier block, counting from the start of
+// bytes and ignoring any partial block at the end. ECB mode encrypts equal plaintext blocks to
+// equal ciphertext blocks, so repeats are its tell, as in challenge 8. Panics if block_size is 0.
+pub fn repeated_blocks(bytes: &[u8], block_size: usize) -> usize {
+    assert!(block_size > 0, "blocks can't be empty");
+    let mut seen = HashSet::new();
+    bytes.chunks_exact(block_size).filter(|block| !seen.insert(*block)).count()
+}
+
+// All the single string statistics at once, for describing a blob of unknown bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteStats {
+    pub len: usize,
+    pub entropy: f64,
+    pub index_of_coincidence: f64,
+    pub uniformity: Uniformity,
+    pub serial_correlation: Option<f64>,
+    // The number of distinct byte values.
+    pub distinct: usize,
+    // The share of bytes that are printable ASCII or whitespace.
+    pub printable: f64,
+}
+
+impl ByteStats {
+    pub fn of(bytes: &[u8]) -> ByteStats {
+        let printable = bytes.iter()
+                             .filter(|&&b| b == b'\t' || b == b'\n' || b == b'\r' ||
+                                           (0x20..0x7f).contains(&b))
+                             .count();
+        ByteStats {
+            len: bytes.len(),
+            entropy: entropy(bytes),
+            index_of_coincidence: index_of_coincidence(bytes),
+            uniformity: chi_squared_uniformity(bytes),
+            serial_correlation: serial_correlation(bytes),
+            distinct: histogram(bytes).iter().filter(|&&c| c > 0).count(),
+            printable: if bytes.is_empty() {
+                0.0
+            } else {
+                printable as f64 / bytes.len() as f64
+            },
+        }
+    }
+}
+
+// Bytes that look random enough for tests, different for each seed. Shared with the tests of the
+// modules built on these statistics.
+#[cfg(test)]
+pub fn pseudo_random(n: usize, seed: u64) -> Vec<u8> {
+    // xorshift64*, which must not start from 0.
+    let mut x: u64 = (seed + 1).wrapping_mul(0x9e3779b97f4a7c15);
+    (0..n)
+        .map(|_| {
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            (x.wrapping_mul(0x2545f4914f6cdd1d) >> 56) as u8
+        })
+        .collect()
 }
 
 #[test]
@@ -7,3 +202,99 @@ fn test_hamming() {
     let d = hamming("this is a test".as_bytes(), "wokka wokka!!!".as_bytes());
     assert_eq!(d, 37);
 }
+
+#[test]
+fn test_hamming_different_lengths() {
+    assert_eq!(hamming(b"ab", b"abcd"), 16);
+    assert_eq!(hamming(b"abcd", b"ab"), 16);
+    assert_eq!(hamming(b"", b"\x00"), 8);
+    assert_eq!(hamming(b"", b""), 0);
+}
+
+#[test]
+fn test_levenshtein() {
+    assert_eq!(levenshtein(b"kitten", b"sitting"), 3);
+    assert_eq!(levenshtein(b"sitting", b"kitten"), 3);
+    assert_eq!(levenshtein(b"", b"abc"), 3);
+    assert_eq!(levenshtein(b"abc", b""), 3);
+    assert_eq!(levenshtein(b"flaw", b"lawn"), 2);
+    assert_eq!(levenshtein(b"same", b"same"), 0);
+}
+
+#[test]
+fn test_histogram_and_entropy() {
+    let h = histogram(b"hello");
+    assert_eq!((h[b'l' as usize], h[b'h' as usize], h[0]), (2, 1, 0));
+
+    // Not -0.0, which compares equal but prints as "-0.00".
+    assert_eq!(entropy(b""), 0.0);
+    assert_eq!(entropy(b"aaaa"), 0.0);
+    assert!(entropy(b"").is_sign_positive());
+    assert!(entropy(b"aaaa").is_sign_positive());
+    assert_eq!(entropy(b"abab"), 1.0);
+    let all: Vec<u8> = (0..=255).collect();
+    assert_eq!(entropy(&all), 8.0);
+    assert!(entropy(&pseudo_random(65536, 0)) > 7.99);
+}
+
+#[test]
+fn test_index_of_coincidence() {
+    assert_eq!(index_of_coincidence(b"a"), 0.0);
+    assert_eq!(index_of_coincidence(b"aaaa"), 1.0);
+    assert_eq!(index_of_coincidence(b"abcd"), 0.0);
+    let text = include_str!("data/english.txt").to_lowercase();
+    assert!((index_of_coincidence(text.as_bytes()) - 0.065).abs() < 0.015);
+    assert!((index_of_coincidence(&pseudo_random(65536, 0)) - 1.0 / 256.0).abs() < 0.0005);
+}
+
+#[test]
+fn test_uniformity() {
+    let random = chi_squared_uniformity(&pseudo_random(65536, 0));
+    assert!(random.p_value > 0.01, "{:?}", random);
+    let text = chi_squared_uniformity(include_str!("data/english.txt").as_bytes());
+    assert!(text.p_value < 1e-9, "{:?}", text);
+    assert_eq!(chi_squared_uniformity(b"").p_value, 1.0);
+
+    // Every value exactly as often as every other fits perfectly.
+    let all: Vec<u8> = (0..=255).cycle().take(2560).collect();
+    assert_eq!(chi_squared_uniformity(&all).chi_squared, 0.0);
+    assert!(chi_squared_uniformity(&all).p_value > 0.999);
+
+    assert!((erfc(0.0) - 1.0).abs() < 1e-7);
+    assert!((erfc(1.0) - 0.157299207).abs() < 1e-7);
+    assert!((erfc(-1.0) - 1.842700793).abs() < 1e-7);
+}
+
+#[test]
+fn test_serial_correlation() {
+    assert_eq!(serial_correlation(b"a"), None);
+    assert_eq!(serial_correlation(b"aaaa"), None);
+    assert!(serial_correlation(&pseudo_random(65536, 0)).unwrap().abs() < 0.02);
+    // A slow ramp is strongly correlated, alternating bytes strongly anti-correlated.
+    let ramp: Vec<u8> = (0..1000u32).map(|i| (i / 4) as u8).collect();
+    assert!(serial_correlation(&ramp).unwrap() > 0.9);
+    assert!(serial_correlation(&[0, 255, 0, 255, 0, 255]).unwrap() < -0.99);
+}
+
+#[test]
+fn test_repeated_blocks() {
+    assert_eq!(repeated_blocks(b"abcdabcdabcdxyz", 4), 2);
+    assert_eq!(repeated_blocks(b"abcdabcdabcdxyz", 8), 0);
+    assert_eq!(repeated_blocks(b"aaaaaaaa", 1), 7);
+    assert_eq!(repeated_blocks(b"", 16), 0);
+    assert_eq!(repeated_blocks(&pseudo_random(4096, 0), 16), 0);
+}
+
+#[test]
+fn test_byte_stats() {
+    let text = ByteStats::of(include_str!("data/english.txt").as_bytes());
+    assert_eq!(text.printable, 1.0);
+    assert!(text.entropy > 3.5 && text.entropy < 5.0);
+    let random = ByteStats::of(&pseudo_random(4096, 0));
+    assert_eq!(random.len, 4096);
+    assert_eq!(random.distinct, 256);
+    assert!(random.entropy > 7.9);
+    assert!(random.printable < 0.5);
+    let empty = ByteStats::of(b"");
+    assert_eq!((empty.len, empty.distinct, empty.printable), (0, 0, 0.0));
+}
@@ -1,47 +1,214 @@
+use std::cmp;
 use std::collections::HashMap;
+use std::f32;
+use std::io::{self, BufRead, Read, Write};
+use std::str;
+use conversions::{hex_to_string, try_string_to_hex};
+use measure::histogram;
 
-#[derive(Debug)]
+// How much each unprintable byte adds to dist_from_bytes, as a fraction of the input. A distance
+// is at most 2, so a plaintext that is all control characters scores twice as badly as one that
+// is printable but has nothing in common with the reference.
+const UNPRINTABLE_PENALTY: f32 = 2.0;
+
+// Printable ASCII, the usual whitespace, and the printable half of Latin-1.
+fn is_printable(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\r' | 0x20..=0x7e | 0xa0..=0xff)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CharFreq {
     counts: HashMap<char, usize>,
     total: usize,
 }
 
+// Counts of letters per 100000 in English, and of spaces.
+const ENGLISH: [(char, usize); 27] = [
+    (' ', 12802),
+    ('e', 12702),
+    ('t', 9056),
+    ('a', 8167),
+    ('o', 7507),
+    ('i', 6966),
+    ('n', 6749),
+    ('s', 6327),
+    ('h', 6094),
+    ('r', 5987),
+    ('d', 4253),
+    ('l', 4025),
+    ('c', 2782),
+    ('u', 2758),
+    ('m', 2406),
+    ('w', 2361),
+    ('f', 2228),
+    ('g', 2015),
+    ('y', 1974),
+    ('p', 1929),
+    ('b', 1492),
+    ('v', 978),
+    ('k', 772),
+    ('j', 153),
+    ('x', 150),
+    ('q', 95),
+    ('z', 74),
+];
+
+// Counts of letters per 100000 in French, with spaces as common as in English.
+const FRENCH: [(char, usize); 40] = [
+    ('e', 14715),
+    (' ', 12802),
+    ('s', 7948),
+    ('a', 7636),
+    ('i', 7529),
+    ('t', 7244),
+    ('n', 7095),
+    ('r', 6693),
+    ('u', 6311),
+    ('o', 5796),
+    ('l', 5456),
+    ('d', 3669),
+    ('c', 3260),
+    ('m', 2968),
+    ('p', 2521),
+    ('v', 1838),
+    ('é', 1504),
+    ('q', 1362),
+    ('f', 1066),
+    ('b', 901),
+    ('g', 866),
+    ('h', 737),
+    ('j', 613),
+    ('à', 486),
+    ('x', 427),
+    ('z', 326),
+    ('è', 271),
+    ('ê', 218),
+    ('y', 128),
+    ('ç', 85),
+    ('k', 74),
+    ('û', 60),
+    ('ù', 58),
+    ('â', 51),
+    ('w', 49),
+    ('î', 45),
+    ('ô', 23),
+    ('œ', 18),
+    ('ë', 8),
+    ('ï', 5),
+];
+
+// Counts of letters per 100000 in German, with spaces as common as in English.
+const GERMAN: [(char, usize); 31] = [
+    ('e', 16396),
+    (' ', 12802),
+    ('n', 9776),
+    ('s', 7270),
+    ('r', 7003),
+    ('i', 6550),
+    ('a', 6516),
+    ('t', 6154),
+    ('d', 5076),
+    ('h', 4577),
+    ('u', 4166),
+    ('l', 3437),
+    ('g', 3009),
+    ('c', 2732),
+    ('o', 2594),
+    ('m', 2534),
+    ('w', 1921),
+    ('b', 1886),
+    ('f', 1656),
+    ('k', 1417),
+    ('z', 1134),
+    ('ü', 995),
+    ('v', 846),
+    ('p', 670),
+    ('ä', 578),
+    ('ö', 443),
+    ('ß', 307),
+    ('j', 268),
+    ('y', 39),
+    ('x', 34),
+    ('q', 18),
+];
+
+// Counts of letters per 100000 in Spanish, with spaces as common as in English.
+const SPANISH: [(char, usize); 34] = [
+    (' ', 12802),
+    ('e', 12181),
+    ('a', 11525),
+    ('o', 8683),
+    ('s', 7977),
+    ('r', 6871),
+    ('n', 6712),
+    ('i', 6247),
+    ('d', 5010),
+    ('l', 4967),
+    ('t', 4632),
+    ('c', 4019),
+    ('m', 3157),
+    ('u', 2927),
+    ('p', 2510),
+    ('b', 2215),
+    ('g', 1768),
+    ('v', 1138),
+    ('y', 1008),
+    ('q', 877),
+    ('ó', 827),
+    ('í', 725),
+    ('h', 703),
+    ('f', 692),
+    ('á', 502),
+    ('j', 493),
+    ('z', 467),
+    ('é', 433),
+    ('ñ', 311),
+    ('x', 215),
+    ('ú', 168),
+    ('w', 17),
+    ('ü', 12),
+    ('k', 11),
+];
+
 impl CharFreq {
     pub fn for_english() -> CharFreq {
+        CharFreq::from_table(&ENGLISH)
+    }
+
+    pub fn for_french() -> CharFreq {
+        CharFreq::from_table(&FRENCH)
+    }
+
+    pub fn for_german() -> CharFreq {
+        CharFreq::from_table(&GERMAN)
+    }
+
+    pub fn for_spanish() -> CharFreq {
+        CharFreq::from_table(&SPANISH)
+    }
+
+    fn from_table(table: &[(char, usize)]) -> CharFreq {
         CharFreq {
-            counts: vec![
-                (' ', 12802),
-                ('e', 12702),
-                ('t', 9056),
-                ('a', 8167),
-                ('o', 7507),
-                ('i', 6966),
-                ('n', 6749),
-                ('s', 6327),
-                ('h', 6094),
-                ('r', 5987),
-                ('d', 4253),
-                ('l', 4025),
-                ('c', 2782),
-                ('u', 2758),
-                ('m', 2406),
-                ('w', 2361),
-                ('f', 2228),
-                ('g', 2015),
-                ('y', 1974),
-                ('p', 1929),
-                ('b', 1492),
-                ('v', 978),
-                ('k', 772),
-                ('j', 153),
-                ('x', 150),
-                ('q', 95),
-                ('z', 74),
-            ].into_iter().collect(),
-            total: 100000,
+            counts: table.iter().cloned().collect(),
+            total: table.iter().map(|&(_, n)| n).sum(),
         }
     }
 
+    // The frequencies of the characters in a sample of text, which can be anything from prose in
+    // another language to JSON or source code.
+    pub fn from_corpus(corpus: &str) -> CharFreq {
+        let mut freq = CharFreq::new();
+        freq.count_all(corpus);
+        freq
+    }
+
+    // Like from_corpus, for UTF-8 text read from r, such as a corpus file.
+    pub fn from_reader<R: Read>(r: R) -> io::Result<CharFreq> {
+        let mut freq = CharFreq::new();
+        freq.count_reader(r)?;
+        Ok(freq)
+    }
+
     pub fn new() -> CharFreq {
         CharFreq { counts: HashMap::new(), total: 0 }
     }
@@ -85,6 +252,421 @@ impl CharFreq {
         other.count_all(s);
         self.dist(&other)
     }
+
+    // Counts all the characters in UTF-8 text read from r, a piece at a time. Text that isn't
+    // UTF-8 is an InvalidData error.
+    pub fn count_reader<R: Read>(&mut self, mut r: R) -> io::Result<()> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "text isn't valid UTF-8");
+        let mut buf = Vec::new();
+        let mut chunk = [0; 8192];
+        loop {
+            let n = match r.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buf.extend_from_slice(&chunk[..n]);
+            // A character split between reads is kept until the rest of it arrives.
+            let valid = match str::from_utf8(&buf) {
+                Ok(s) => s.len(),
+                Err(ref e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => return Err(invalid()),
+            };
+            self.count_all(str::from_utf8(&buf[..valid]).unwrap());
+            buf.drain(..valid);
+        }
+        if buf.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+
+    // Adds the counts from other to these, as if everything other counted had been counted here.
+    pub fn merge(&mut self, other: &CharFreq) {
+        for (&c, &n) in other.counts.iter() {
+            *self.counts.entry(c).or_insert(0) += n;
+        }
+        self.total += other.total;
+    }
+
+    // Writes the table out as text: a header line, then the code point of each character in hex
+    // with its count, one to a line.
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut counts: Vec<(&char, &usize)> = self.counts.iter().collect();
+        counts.sort();
+        writeln!(w, "charfreq")?;
+        for (&c, n) in counts {
+            writeln!(w, "{:x} {}", c as u32, n)?;
+        }
+        Ok(())
+    }
+
+    // Reads a table written by save.
+    pub fn load<R: BufRead>(r: R) -> io::Result<CharFreq> {
+        let bad = |line: usize, what: &str| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("bad frequency table at line {}: {}", line, what))
+        };
+        let mut lines = r.lines();
+        if lines.next().unwrap_or(Ok(String::new()))? != "charfreq" {
+            return Err(bad(1, "expected a charfreq header"));
+        }
+        let mut freq = CharFreq::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let entry = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [c, n] => {
+                    u32::from_str_radix(c, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .and_then(|c| n.parse::<usize>().ok().map(|n| (c, n)))
+                }
+                _ => None,
+            };
+            let (c, n) = entry.ok_or_else(|| bad(i + 2, "expected a code point and a count"))?;
+            *freq.counts.entry(c).or_insert(0) += n;
+            freq.total += n;
+        }
+        Ok(freq)
+    }
+
+    // Counts each byte as the Latin-1 character it encodes, so any bytes can be counted whether or
+    // not they are valid UTF-8.
+    pub fn count_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.count(b as char);
+        }
+    }
+
+    // Like dist_from_string, for bytes that needn't be text. Unprintable bytes are penalised on
+    // top of their effect on the distribution, so a plaintext with the odd stray byte can still
+    // score well while binary noise can't. Empty input scores as badly as possible.
+    pub fn dist_from_bytes(&self, bytes: &[u8]) -> f32 {
+        if bytes.is_empty() {
+            return f32::MAX;
+        }
+        let mut other = CharFreq::new();
+        other.count_bytes(bytes);
+        let unprintable = bytes.iter().filter(|&&b| !is_printable(b)).count();
+        self.dist(&other) + UNPRINTABLE_PENALTY * unprintable as f32 / bytes.len() as f32
+    }
+}
+
+impl Default for CharFreq {
+    fn default() -> CharFreq {
+        CharFreq::new()
+    }
+}
+
+// Something that can say how much like a reference text some bytes are. Lower scores are better,
+// and empty input scores f32::MAX. Scorers are shared between the threads of a search, so must be
+// Sync.
+pub trait Scorer: Sync {
+    fn score(&self, bytes: &[u8]) -> f32;
+}
+
+impl Scorer for CharFreq {
+    fn score(&self, bytes: &[u8]) -> f32 {
+        self.dist_from_bytes(bytes)
+    }
+}
+
+// The languages there are built in character frequency profiles for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub fn all() -> &'static [Language] {
+        const ALL: [Language; 4] =
+            [Language::English, Language::French, Language::German, Language::Spanish];
+        &ALL
+    }
+
+    pub fn profile(&self) -> CharFreq {
+        match *self {
+            Language::English => CharFreq::for_english(),
+            Language::French => CharFreq::for_french(),
+            Language::German => CharFreq::for_german(),
+            Language::Spanish => CharFreq::for_spanish(),
+        }
+    }
+}
+
+// Every built in language with the distance of text from its profile, closest first.
+pub fn rank_languages(text: &str) -> Vec<(Language, f32)> {
+    let mut ranked: Vec<(Language, f32)> = Language::all()
+        .iter()
+        .map(|&l| (l, l.profile().dist_from_string(text)))
+        .collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked
+}
+
+// The built in language whose profile text is closest to.
+pub fn identify_language(text: &str) -> Language {
+    rank_languages(text)[0].0
+}
+
+// Counts per 100000 bytes of English prose for the bytes CharFreq::for_english leaves out, other
+// than capitals, which ByteFreq::for_english takes to be a twentieth as common as lowercase.
+const ENGLISH_OTHER_BYTES: [(u8, usize); 21] = [
+    (b'.', 650), (b',', 610), (b'\n', 400), (b'"', 260), (b'\'', 240), (b'-', 150), (b'?', 50),
+    (b';', 30), (b':', 30), (b'!', 30), (b'(', 10), (b')', 10),
+    (b'0', 60), (b'1', 50), (b'2', 40), (b'3', 30), (b'4', 30), (b'5', 30), (b'6', 20), (b'7', 20),
+    (b'8', 20),
+];
+
+// The weight given to each byte value on top of its count, so that bytes never seen in the
+// reference are unlikely rather than impossible.
+const SMOOTHING: f64 = 0.5;
+
+// The probability of each byte value in a reference text, for scorers that look at every byte
+// rather than only at letters as CharFreq does.
+#[derive(Debug, Clone)]
+pub struct ByteFreq {
+    probs: [f64; 256],
+}
+
+impl ByteFreq {
+    pub fn for_english() -> ByteFreq {
+        let en = CharFreq::for_english();
+        let mut counts = [0; 256];
+        for (&c, &n) in en.counts.iter() {
+            counts[c as usize] += n;
+            if c.is_ascii_lowercase() {
+                counts[c.to_ascii_uppercase() as usize] += n / 20;
+            }
+        }
+        for &(b, n) in ENGLISH_OTHER_BYTES.iter() {
+            counts[b as usize] += n;
+        }
+        ByteFreq::from_counts(&counts)
+    }
+
+    // The distribution of the bytes in a sample, smoothed so that no byte value is impossible.
+    pub fn from_sample(sample: &[u8]) -> ByteFreq {
+        ByteFreq::from_counts(&histogram(sample))
+    }
+
+    pub fn from_counts(counts: &[usize; 256]) -> ByteFreq {
+        let total = counts.iter().sum::<usize>() as f64 + 256.0 * SMOOTHING;
+        let mut probs = [0.0; 256];
+        for (p, &n) in probs.iter_mut().zip(counts.iter()) {
+            *p = (n as f64 + SMOOTHING) / total;
+        }
+        ByteFreq { probs }
+    }
+
+    pub fn probability(&self, b: u8) -> f64 {
+        self.probs[b as usize]
+    }
+
+    // Pearson's chi-squared statistic for bytes having been drawn from this distribution.
+    pub fn chi_squared(&self, bytes: &[u8]) -> f32 {
+        if bytes.is_empty() {
+            return f32::MAX;
+        }
+        let n = bytes.len() as f64;
+        let chi: f64 = histogram(bytes)
+            .iter()
+            .zip(self.probs.iter())
+            .map(|(&o, &p)| {
+                let expected = n * p;
+                (o as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        chi as f32
+    }
+
+    // The Bhattacharyya distance between the distribution of bytes and this one: 0 when they are
+    // the same, growing without bound as they overlap less.
+    pub fn bhattacharyya(&self, bytes: &[u8]) -> f32 {
+        if bytes.is_empty() {
+            return f32::MAX;
+        }
+        let n = bytes.len() as f64;
+        let coefficient: f64 = histogram(bytes)
+            .iter()
+            .zip(self.probs.iter())
+            .map(|(&o, &p)| (o as f64 / n * p).sqrt())
+            .sum();
+        -coefficient.ln() as f32
+    }
+
+    // The negative log-likelihood of bytes under this distribution, in bits per byte, so that
+    // inputs of different lengths can be compared.
+    pub fn log_likelihood(&self, bytes: &[u8]) -> f32 {
+        if bytes.is_empty() {
+            return f32::MAX;
+        }
+        let bits: f64 = bytes.iter().map(|&b| -self.probability(b).log2()).sum();
+        (bits / bytes.len() as f64) as f32
+    }
+}
+
+// Scores bytes by ByteFreq::chi_squared.
+#[derive(Debug, Clone)]
+pub struct ChiSquared(pub ByteFreq);
+
+// Scores bytes by ByteFreq::bhattacharyya.
+#[derive(Debug, Clone)]
+pub struct Bhattacharyya(pub ByteFreq);
+
+// Scores bytes by ByteFreq::log_likelihood.
+#[derive(Debug, Clone)]
+pub struct LogLikelihood(pub ByteFreq);
+
+impl Scorer for ChiSquared {
+    fn score(&self, bytes: &[u8]) -> f32 {
+        self.0.chi_squared(bytes)
+    }
+}
+
+impl Scorer for Bhattacharyya {
+    fn score(&self, bytes: &[u8]) -> f32 {
+        self.0.bhattacharyya(bytes)
+    }
+}
+
+impl Scorer for LogLikelihood {
+    fn score(&self, bytes: &[u8]) -> f32 {
+        self.0.log_likelihood(bytes)
+    }
+}
+
+// The share of one occurrence an n-gram that was never seen in training is taken to have had, so
+// that unseen n-grams are unlikely rather than impossible.
+const UNSEEN_NGRAM: f64 = 0.01;
+
+// A model of text as overlapping runs of n bytes, for n from 2 to 4, trained by counting the runs
+// in a corpus. Scoring by the log-probabilities of each bigram, trigram or quadgram in a text
+// captures spelling and word boundaries that letter frequencies alone can't, so it ranks short
+// texts much better than CharFreq does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NgramModel {
+    n: usize,
+    counts: HashMap<Vec<u8>, u64>,
+    total: u64,
+}
+
+impl NgramModel {
+    // An untrained model of n-grams. Panics unless n is 2, 3 or 4.
+    pub fn new(n: usize) -> NgramModel {
+        assert!((2..=4).contains(&n), "n-gram models must be of 2, 3 or 4 bytes");
+        NgramModel { n, counts: HashMap::new(), total: 0 }
+    }
+
+    pub fn from_corpus(n: usize, corpus: &[u8]) -> NgramModel {
+        let mut model = NgramModel::new(n);
+        model.train(corpus);
+        model
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    // Counts every n-gram in corpus.
+    pub fn train(&mut self, corpus: &[u8]) {
+        for gram in corpus.windows(self.n) {
+            *self.counts.entry(gram.to_vec()).or_insert(0) += 1;
+            self.total += 1;
+        }
+    }
+
+    // Counts every n-gram in everything read from r, such as a corpus file, without reading it
+    // all into memory at once.
+    pub fn train_from_reader<R: Read>(&mut self, mut r: R) -> io::Result<()> {
+        let mut buf = Vec::new();
+        let mut chunk = [0; 8192];
+        loop {
+            let n = match r.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buf.extend_from_slice(&chunk[..n]);
+            self.train(&buf);
+            // Keep the end of this chunk for the n-grams that span into the next one.
+            let keep = cmp::min(buf.len(), self.n - 1);
+            let start = buf.len() - keep;
+            buf.drain(..start);
+        }
+    }
+
+    // The base 2 log of the probability of the given n-gram. A model that hasn't been trained
+    // knows of nothing, so gives every n-gram probability 0, a log of minus infinity.
+    pub fn log_probability(&self, gram: &[u8]) -> f64 {
+        if self.total == 0 {
+            return f64::NEG_INFINITY;
+        }
+        let count = self.counts.get(gram).map_or(UNSEEN_NGRAM, |&c| c as f64);
+        (count / self.total as f64).log2()
+    }
+
+    // Writes the model out as text, a header line giving n and then each n-gram in hex with its
+    // count, one to a line.
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut grams: Vec<(&Vec<u8>, &u64)> = self.counts.iter().collect();
+        grams.sort();
+        writeln!(w, "ngrams {}", self.n)?;
+        for (gram, count) in grams {
+            writeln!(w, "{} {}", hex_to_string(gram), count)?;
+        }
+        Ok(())
+    }
+
+    // Reads a model written by save.
+    pub fn load<R: BufRead>(r: R) -> io::Result<NgramModel> {
+        let bad = |line: usize, what: &str| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("bad n-gram model at line {}: {}", line, what))
+        };
+        let mut lines = r.lines();
+        let header = lines.next().unwrap_or(Ok(String::new()))?;
+        let n = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            ["ngrams", n] => n.parse().ok().filter(|n| *n >= 2 && *n <= 4),
+            _ => None,
+        };
+        let mut model = NgramModel::new(n.ok_or_else(|| bad(1, "expected an ngrams header"))?);
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let (gram, count) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [gram, count] => (try_string_to_hex(gram), count.parse::<u64>()),
+                _ => return Err(bad(i + 2, "expected an n-gram and a count")),
+            };
+            match (gram, count) {
+                (Ok(ref gram), Ok(count)) if gram.len() == model.n => {
+                    *model.counts.entry(gram.clone()).or_insert(0) += count;
+                    model.total += count;
+                }
+                _ => return Err(bad(i + 2, "expected an n-gram and a count")),
+            }
+        }
+        Ok(model)
+    }
+}
+
+impl Scorer for NgramModel {
+    // The average number of bits each n-gram of bytes takes under the model. Input shorter than
+    // an n-gram, or a model that hasn't been trained, scores as badly as possible.
+    fn score(&self, bytes: &[u8]) -> f32 {
+        if bytes.len() < self.n || self.total == 0 {
+            return f32::MAX;
+        }
+        let grams = bytes.windows(self.n);
+        let count = grams.len();
+        let bits: f64 = grams.map(|g| -self.log_probability(g)).sum();
+        (bits / count as f64) as f32
+    }
 }
 
 #[test]
@@ -116,7 +698,9 @@ fn test_dist() {
 #[test]
 fn test_english() {
     let en = CharFreq::for_english();
-    assert_eq!(*en.counts.get(&'e').unwrap() as f32 / en.total as f32, 0.12702);
+    assert_eq!(en.total, en.counts.values().sum());
+    let letters = en.total - en.counts[&' '];
+    assert_eq!(en.counts[&'e'] as f32 / letters as f32, 0.12702);
 
     let mut text = CharFreq::new();
     let mut gibberish = CharFreq::new();
@@ -126,3 +710,188 @@ fn test_english() {
                          qvfgevohgvba guna fbzr tvoorevfu bs gur fnzr yratgu");
     assert!(text.dist(&en) < (gibberish.dist(&en) - 0.5));
 }
+
+#[test]
+fn test_dist_from_bytes() {
+    let en = CharFreq::for_english();
+    let text = b"this is a test of english text";
+    // Latin-1 accents and a stray control byte cost a little, but not as much as noise.
+    let latin1 = b"this is a caf\xe9 test of english\x01";
+    let noise: Vec<u8> = (0..30u32).map(|i| (i * 97 + 13) as u8).collect();
+    assert_eq!(en.dist_from_bytes(text), en.dist_from_string("this is a test of english text"));
+    assert!(en.dist_from_bytes(text) < en.dist_from_bytes(latin1));
+    assert!(en.dist_from_bytes(latin1) < 1.0);
+    assert!(en.dist_from_bytes(&noise) > 2.0);
+    assert_eq!(en.dist_from_bytes(b""), f32::MAX);
+}
+
+#[test]
+fn test_byte_freq() {
+    let en = ByteFreq::for_english();
+    assert!(((0..256).map(|b| en.probability(b as u8)).sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(en.probability(b'e') > en.probability(b'E'));
+    assert!(en.probability(b'E') > en.probability(0));
+    assert!(en.probability(0) > 0.0);
+
+    let sample = ByteFreq::from_sample(b"aab");
+    assert_eq!(sample.probability(b'a'), 2.5 / (3.0 + 128.0));
+    assert!(sample.log_likelihood(b"aab") < sample.log_likelihood(b"xyz"));
+    assert!(sample.bhattacharyya(b"aab") < sample.bhattacharyya(b"abb"));
+}
+
+#[test]
+fn test_scorers() {
+    let text = "This is a test to see that real English is closer to the English \
+                distribution than some gibberish of the same length.";
+    let gibberish = "Guvf vf n grfg gb frr gung erny Ratyvfu vf pybfre gb gur Ratyvfu \
+                     qvfgevohgvba guna fbzr tvoorevfu bs gur fnzr yratgu.";
+    // The same text with the case of every letter swapped, which CharFreq can't tell apart.
+    let swapped: String = text.chars()
+                              .map(|c| {
+                                  if c.is_ascii_lowercase() {
+                                      c.to_ascii_uppercase()
+                                  } else {
+                                      c.to_ascii_lowercase()
+                                  }
+                              })
+                              .collect();
+    let scorers: Vec<Box<dyn Scorer>> = vec![Box::new(ChiSquared(ByteFreq::for_english())),
+                                             Box::new(Bhattacharyya(ByteFreq::for_english())),
+                                             Box::new(LogLikelihood(ByteFreq::for_english()))];
+    for scorer in scorers.iter() {
+        assert!(scorer.score(text.as_bytes()) < scorer.score(gibberish.as_bytes()));
+        assert!(scorer.score(text.as_bytes()) < scorer.score(swapped.as_bytes()));
+        assert_eq!(scorer.score(b""), f32::MAX);
+    }
+    let en = CharFreq::for_english();
+    assert_eq!(en.score(text.as_bytes()), en.score(swapped.as_bytes()));
+}
+
+#[test]
+fn test_ngram_model() {
+    let mut model = NgramModel::new(2);
+    model.train(b"abab");
+    assert_eq!(model.log_probability(b"ab"), (2.0f64 / 3.0).log2());
+    assert_eq!(model.log_probability(b"ba"), (1.0f64 / 3.0).log2());
+    assert_eq!(model.log_probability(b"zz"), (0.01f64 / 3.0).log2());
+    assert!(model.score(b"abab") < model.score(b"bbbb"));
+    assert_eq!(model.score(b"a"), f32::MAX);
+    assert_eq!(NgramModel::new(3).score(b"abc"), f32::MAX);
+    assert_eq!(NgramModel::new(3).log_probability(b"abc"), f64::NEG_INFINITY);
+
+    // Reading in small pieces counts the n-grams that span them.
+    let corpus = include_str!("data/english.txt").as_bytes();
+    let mut streamed = NgramModel::new(4);
+    streamed.train_from_reader(corpus).unwrap();
+    assert_eq!(streamed, NgramModel::from_corpus(4, corpus));
+}
+
+#[test]
+fn test_ngram_save_and_load() {
+    let model = NgramModel::from_corpus(3, include_str!("data/english.txt").as_bytes());
+    let mut saved = Vec::new();
+    model.save(&mut saved).unwrap();
+    assert!(saved.starts_with(b"ngrams 3\n"));
+    assert_eq!(NgramModel::load(&saved[..]).unwrap(), model);
+
+    for bad in ["", "ngrams 5\n", "ngrams 2\n6162 1\n616263 1\n", "ngrams 2\n6162 x\n"].iter() {
+        let e = NgramModel::load(bad.as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_ngram_scoring() {
+    let corpus = include_str!("data/english.txt").as_bytes();
+    let text = b"the lighthouse keeper climbed the stairs every night";
+    let shuffled = b"eht esuohthgil repeek debmilc eht sriats yreve thgin";
+    for n in 2..5 {
+        let model = NgramModel::from_corpus(n, corpus);
+        assert!(model.score(text) < model.score(shuffled), "{}-grams", n);
+    }
+}
+
+#[test]
+fn test_ngram_single_byte_xor_lines() {
+    use conversions::string_to_hex;
+    use crack::rank_xor_keys_with;
+
+    // Unigram scoring needs every line compared with the rest to pick out the English one in
+    // challenge 4, but under quadgrams it is simply the best scoring line.
+    let model = NgramModel::from_corpus(4, include_str!("data/english.txt").as_bytes());
+    let best = include_str!("challenges/data/4.txt")
+                   .lines()
+                   .filter_map(|line| rank_xor_keys_with(&string_to_hex(line), 1, &model).pop())
+                   .min_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+                   .unwrap();
+    assert_eq!(best.plaintext, b"Now that the party is jumping\n".to_vec());
+}
+
+#[test]
+fn test_from_corpus() {
+    let corpus = include_str!("data/english.txt");
+    let freq = CharFreq::from_corpus(corpus);
+    assert_eq!(freq.total, corpus.chars().count());
+    assert_eq!(freq.counts[&'e'], corpus.chars().filter(|&c| c == 'e' || c == 'E').count());
+    assert_eq!(CharFreq::from_reader(corpus.as_bytes()).unwrap(), freq);
+
+    // A character split across reads is still counted once.
+    struct OneByte<'a>(&'a [u8]);
+    impl<'a> Read for OneByte<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = cmp::min(1, self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+    let accents = CharFreq::from_reader(OneByte("déjà vu".as_bytes())).unwrap();
+    assert_eq!(accents, CharFreq::from_corpus("déjà vu"));
+    for bad in [&b"caf\xe9"[..], b"\xff abc"].iter() {
+        let e = CharFreq::from_reader(*bad).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_merge_save_and_load() {
+    let mut a = CharFreq::from_corpus("aab");
+    a.merge(&CharFreq::from_corpus("bc\n"));
+    assert_eq!(a, CharFreq::from_corpus("aabbc\n"));
+
+    let mut saved = Vec::new();
+    a.save(&mut saved).unwrap();
+    assert_eq!(saved, b"charfreq\na 1\n61 2\n62 2\n63 1\n".to_vec());
+    assert_eq!(CharFreq::load(&saved[..]).unwrap(), a);
+    let profile = CharFreq::for_german();
+    let mut saved = Vec::new();
+    profile.save(&mut saved).unwrap();
+    assert_eq!(CharFreq::load(&saved[..]).unwrap(), profile);
+
+    for bad in ["", "charfreq\n61\n", "charfreq\nd800 1\n", "charfreq\n61 -1\n"].iter() {
+        let e = CharFreq::load(bad.as_bytes()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_identify_language() {
+    let samples = [(Language::English,
+                    "The quick brown fox jumps over the lazy dog while the farmer watches from \
+                     the porch of his house"),
+                   (Language::French,
+                    "Le renard brun rapide saute par-dessus le chien paresseux pendant que le \
+                     fermier regarde depuis le porche de sa maison"),
+                   (Language::German,
+                    "Der schnelle braune Fuchs springt über den faulen Hund, während der Bauer \
+                     von der Veranda seines Hauses aus zuschaut"),
+                   (Language::Spanish,
+                    "El rápido zorro marrón salta sobre el perro perezoso mientras el granjero \
+                     mira desde el porche de su casa")];
+    for &(language, text) in samples.iter() {
+        assert_eq!(identify_language(text), language, "{}", text);
+        let ranked = rank_languages(text);
+        assert_eq!(ranked.len(), Language::all().len());
+        assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+}
@@ -0,0 +1,38 @@
+extern crate cryptopals;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, stdin, stdout, Read, Write};
+use std::process;
+use cryptopals::triage::triage;
+
+const USAGE: &str = "\
+Usage:
+    triage [<file>]     report what kind of cipher or encoding the file, or stdin, is likely in
+
+Runs the encoding detector, byte statistics, single byte and repeating key xor cracking and ECB
+block repeat counting over the input, and ranks the families it could belong to.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut input = Vec::new();
+    let read = match args.iter().map(|s| &s[..]).collect::<Vec<_>>()[..] {
+        [] | ["-"] => stdin().read_to_end(&mut input),
+        ["-h"] | ["--help"] => {
+            let _ = writeln!(&mut stdout(), "{}", USAGE);
+            return;
+        }
+        [path] if !path.starts_with('-') => {
+            File::open(path).and_then(|mut f| f.read_to_end(&mut input))
+        }
+        _ => {
+            let _ = writeln!(&mut io::stderr(), "{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = read {
+        let _ = writeln!(&mut io::stderr(), "triage: {}", e);
+        process::exit(1);
+    }
+    let _ = write!(&mut stdout(), "{}", triage(&input));
+}
@@ -1,23 +1,330 @@
 extern crate cryptopals;
 
-#[cfg(not(test))]
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, stdin, stdout, BufWriter, ErrorKind, Read, Write};
+use std::process;
+use cryptopals::combine::RepeatingXor;
+use cryptopals::conversions::{detect_and_decode, hex_to_string, try_base64_to_hex_with,
+                              try_string_to_hex_with, Base64, Base64Mode, HexMode};
+use cryptopals::conversions::stream::{Base64Reader, Base64Writer, HexReader, HexWriter};
+use cryptopals::crack::break_repeating_xor;
+
+const USAGE: &str = "\
+Usage:
+    xor [encrypt] [options] <key>   repeating key xor stdin with the key
+    xor decrypt [options] <key>     the same, but reading hex and writing raw bytes by default
+    xor crack [options]             find the key for ciphertext on stdin and print the plaintext
+
+The key can be given as the last argument, or with one of:
+    -k, --key <text>          the UTF-8 bytes of text
+    --key-hex <hex>           hex encoded key bytes
+    --key-base64 <base64>     base64 encoded key bytes
+    --key-file <path>         the contents of a file
+
+Options:
+    -i, --input <format>      how stdin is encoded: raw, hex or base64
+    -o, --output <format>     how to encode the output: raw, hex or base64
+    --keep-newline            don't drop a linefeed at the end of raw input
+    -h, --help                show this message
+
+encrypt reads raw and writes hex, decrypt reads hex and writes raw, and crack works out whether
+its input is hex, base64 or raw and writes raw, unless told otherwise. The key crack finds is
+printed to stderr.";
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Encrypt,
+    Decrypt,
+    Crack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Raw,
+    Hex,
+    Base64,
+}
+
+impl Format {
+    fn parse(s: &str) -> ::std::result::Result<Format, String> {
+        match s {
+            "raw" => Ok(Format::Raw),
+            "hex" => Ok(Format::Hex),
+            "base64" => Ok(Format::Base64),
+            _ => Err(format!("unknown format {:?}, expected raw, hex or base64", s)),
+        }
+    }
+}
+
+struct Options {
+    command: Command,
+    key: Option<Vec<u8>>,
+    input: Option<Format>,
+    output: Option<Format>,
+    keep_newline: bool,
+}
+
+// Reasons to stop before doing anything. Bad arguments exit with status 2, and help with 0.
+enum ArgsError {
+    Help,
+    Bad(String),
+}
+
+impl<T: Into<String>> From<T> for ArgsError {
+    fn from(s: T) -> ArgsError {
+        ArgsError::Bad(s.into())
+    }
+}
+
+fn decode_key(flag: &str, value: &str) -> ::std::result::Result<Vec<u8>, String> {
+    let key = match flag {
+        "-k" | "--key" => Ok(value.as_bytes().to_vec()),
+        "--key-hex" => {
+            try_string_to_hex_with(value, HexMode::lenient()).map_err(|e| e.to_string())
+        }
+        "--key-base64" => {
+            try_base64_to_hex_with(value, Base64Mode::lenient()).map_err(|e| e.to_string())
+        }
+        "--key-file" => {
+            let mut v = Vec::new();
+            File::open(value)
+                .and_then(|mut f| f.read_to_end(&mut v))
+                .map(|_| v)
+                .map_err(|e| format!("{}: {}", value, e))
+        }
+        _ => unreachable!(),
+    };
+    key.map_err(|e| format!("bad key for {}: {}", flag, e))
+}
+
+fn parse_args(args: &[String]) -> ::std::result::Result<Options, ArgsError> {
+    let mut opts = Options {
+        command: Command::Encrypt,
+        key: None,
+        input: None,
+        output: None,
+        keep_newline: false,
+    };
+    let mut args = args.iter().map(|s| &s[..]).peekable();
+    let command = match args.peek() {
+        Some(&"encrypt") => Some(Command::Encrypt),
+        Some(&"decrypt") => Some(Command::Decrypt),
+        Some(&"crack") => Some(Command::Crack),
+        _ => None,
+    };
+    if let Some(command) = command {
+        opts.command = command;
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        match arg {
+            "-h" | "--help" => return Err(ArgsError::Help),
+            "--keep-newline" => opts.keep_newline = true,
+            "-i" | "--input" | "-o" | "--output" => {
+                let value = args.next().ok_or(format!("{} needs a format", arg))?;
+                let format = Some(Format::parse(value)?);
+                if arg == "-i" || arg == "--input" {
+                    opts.input = format;
+                } else {
+                    opts.output = format;
+                }
+            }
+            "-k" | "--key" | "--key-hex" | "--key-base64" | "--key-file" => {
+                let value = args.next().ok_or(format!("{} needs a value", arg))?;
+                if opts.key.is_some() {
+                    return Err("only one key can be given".into());
+                }
+                opts.key = Some(decode_key(arg, value)?);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("unknown option {}", arg).into())
+            }
+            key => {
+                if opts.key.is_some() {
+                    return Err("only one key can be given".into());
+                }
+                opts.key = Some(key.as_bytes().to_vec());
+            }
+        }
+    }
+
+    match (opts.command, opts.key.as_ref()) {
+        (Command::Crack, Some(_)) => return Err("crack doesn't take a key".into()),
+        (Command::Crack, None) => (),
+        (_, None) => return Err("a key is required".into()),
+        (_, Some(key)) if key.is_empty() => return Err("the key can't be empty".into()),
+        _ => (),
+    }
+    Ok(opts)
+}
+
+// Where output goes, encoded as asked for. Encoders that hold data back are finished explicitly
+// so that errors writing the last of it are reported. The base64 writer carries its alphabet and
+// decoding table, so is boxed to keep the others small.
+enum Output<W: Write> {
+    Raw(W),
+    Hex(HexWriter<W>),
+    Base64(Box<Base64Writer<W>>),
+}
+
+impl<W: Write> Output<W> {
+    fn new(inner: W, format: Format) -> Output<W> {
+        match format {
+            Format::Raw => Output::Raw(inner),
+            Format::Hex => Output::Hex(HexWriter::new(inner)),
+            Format::Base64 => {
+                Output::Base64(Box::new(Base64Writer::new(inner, Base64::standard())))
+            }
+        }
+    }
+
+    // Finishes the encoding, ending text output with a newline, and flushes.
+    fn finish(self) -> io::Result<()> {
+        let (mut inner, text) = match self {
+            Output::Raw(w) => (w, false),
+            Output::Hex(w) => (w.into_inner(), true),
+            Output::Base64(w) => (w.finish()?, true),
+        };
+        if text {
+            inner.write_all(b"\n")?;
+        }
+        inner.flush()
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Output::Raw(ref mut w) => w.write(buf),
+            Output::Hex(ref mut w) => w.write(buf),
+            Output::Base64(ref mut w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Output::Raw(ref mut w) => w.flush(),
+            Output::Hex(ref mut w) => w.flush(),
+            Output::Base64(ref mut w) => w.flush(),
+        }
+    }
+}
+
+// Decodes input in the given format as it is read.
+fn decoding_reader<'a, R: Read + 'a>(input: R, format: Format) -> Box<dyn Read + 'a> {
+    match format {
+        Format::Raw => Box::new(input),
+        Format::Hex => Box::new(HexReader::new(input, HexMode::lenient())),
+        Format::Base64 => {
+            Box::new(Base64Reader::new(input, Base64::standard(), Base64Mode::lenient()))
+        }
+    }
+}
+
+// Xors chunks from input with the key and writes them to out as they arrive. If strip_linefeed is
+// set a linefeed at the very end of input is dropped; one at the end of a chunk is held back until
+// we know whether more input follows.
+fn xor_stream<R: Read, W: Write>(key: &[u8], mut input: R, out: &mut W, strip_linefeed: bool)
+                                 -> Result<()> {
+    let mut xor = RepeatingXor::new(key);
+    let mut buf = vec![0; 64 * 1024];
+    let mut held_linefeed = false;
+    loop {
+        let mut n = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if held_linefeed {
+            let mut lf = [10];
+            xor.apply(&mut lf);
+            out.write_all(&lf)?;
+        }
+        held_linefeed = strip_linefeed && buf[n - 1] == 10;
+        if held_linefeed {
+            n -= 1;
+        }
+        xor.apply(&mut buf[..n]);
+        out.write_all(&buf[..n])?;
+    }
+    Ok(())
+}
+
+fn run_xor(opts: &Options) -> Result<()> {
+    let (input, output) = match opts.command {
+        Command::Decrypt => (Format::Hex, Format::Raw),
+        _ => (Format::Raw, Format::Hex),
+    };
+    let input = opts.input.unwrap_or(input);
+    let output = opts.output.unwrap_or(output);
+    let strip_linefeed = input == Format::Raw && !opts.keep_newline;
+
+    let stdin = stdin();
+    let stdout = stdout();
+    let mut out = Output::new(BufWriter::new(stdout.lock()), output);
+    xor_stream(opts.key.as_ref().unwrap(),
+               decoding_reader(stdin.lock(), input),
+               &mut out,
+               strip_linefeed)?;
+    out.finish()?;
+    Ok(())
+}
+
+fn crack(opts: &Options) -> Result<()> {
+    let mut input = Vec::new();
+    let cipher = match opts.input {
+        Some(format) => {
+            decoding_reader(stdin(), format).read_to_end(&mut input)?;
+            input
+        }
+        None => {
+            stdin().read_to_end(&mut input)?;
+            let detected = detect_and_decode(&input);
+            writeln!(&mut io::stderr(), "ciphertext read as {:?}", detected.encoding)?;
+            detected.bytes
+        }
+    };
+    if cipher.is_empty() {
+        return Err("no ciphertext on stdin".into());
+    }
+
+    let result = break_repeating_xor(&cipher);
+    writeln!(&mut io::stderr(),
+             "key: {} {:?}",
+             hex_to_string(&result.key),
+             String::from_utf8_lossy(&result.key))?;
+    let stdout = stdout();
+    let mut out = Output::new(BufWriter::new(stdout.lock()), opts.output.unwrap_or(Format::Raw));
+    out.write_all(&result.plaintext)?;
+    out.finish()?;
+    Ok(())
+}
+
 fn main() {
-    use std::env;
-    use std::io::{stdin, stdout, Read, Write};
-    use cryptopals::conversions::hex_to_string;
-    use cryptopals::combine::xor_each;
-
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
-        panic!("Requires one argument which is the key with which to do repeating key xor \
-                over stdin's bytes");
-    }
-    let key: Vec<u8> = args.get(1).unwrap().bytes().collect();
-    let mut stdin: Vec<u8> = stdin().bytes().map(|b| b.unwrap()).collect();
-    if *stdin.last().unwrap() == 10 { // Linefeed at end EOF
-        stdin.pop();
-    }
-    let mut stdout = stdout();
-    write!(&mut stdout, "{}", hex_to_string(&xor_each(&stdin, &key))).unwrap();
-    write!(&mut stdout, "\n").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
+        Err(ArgsError::Help) => {
+            let _ = writeln!(&mut stdout(), "{}", USAGE);
+            return;
+        }
+        Err(ArgsError::Bad(msg)) => {
+            let _ = writeln!(&mut io::stderr(), "xor: {}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    let result = match opts.command {
+        Command::Crack => crack(&opts),
+        _ => run_xor(&opts),
+    };
+    if let Err(e) = result {
+        let _ = writeln!(&mut io::stderr(), "xor: {}", e);
+        process::exit(1);
+    }
 }
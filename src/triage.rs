@@ -0,0 +1,318 @@
+// First look at a blob of unknown provenance: works out how it is encoded, then runs the byte
+// statistics and the Set 1 attacks over what it decodes to and ranks the families of cipher it
+// most likely belongs to, so there's no need to try each tool by hand.
+//
+// Confidences are heuristics between 0 and 1 for ranking, not probabilities. The English based
+// ones come from CharFreq distances: a few hundred bytes of English are usually within 0.6 of the
+// English table and noise is well over 1.6, so distances are mapped linearly from 1 at 0.6 to 0 at
+// 1.6.
+//
+// The xor attacks and the key size ranking are the slow part, so they only look at the first
+// SAMPLE_LEN bytes. That's plenty to find a key or a key size, and keeps triage of a large capture
+// quick; the report says when the input was cut short.
+
+use std::fmt;
+use conversions::{detect_and_decode, Encoding};
+use crack::{break_repeating_xor, rank_key_sizes, rank_xor_keys, KeySize, KeySizeMethod};
+use detect::{infer_block_size, BLOCK_SIZES};
+use measure::{repeated_blocks, ByteStats};
+use text::CharFreq;
+
+// The fewest bytes per key byte break_repeating_xor needs before its plaintexts mean anything.
+// With less, every column is short enough to fit some key to English by chance.
+const MIN_BYTES_PER_KEY_BYTE: usize = 8;
+
+// The most bytes the xor attacks and the key size ranking are run on.
+const SAMPLE_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    // Readable English as it is.
+    Plaintext,
+    SingleByteXor,
+    RepeatingXor,
+    // A block cipher in ECB mode, with the given block size in bytes.
+    Ecb(usize),
+    // Indistinguishable from random bytes: a stream cipher, a block cipher in a chaining mode, or
+    // compressed data.
+    Random,
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Family::Plaintext => write!(f, "plaintext"),
+            Family::SingleByteXor => write!(f, "single byte xor"),
+            Family::RepeatingXor => write!(f, "repeating key xor"),
+            Family::Ecb(size) => write!(f, "ECB mode, {} byte blocks", size),
+            Family::Random => write!(f, "random looking"),
+        }
+    }
+}
+
+// One family the input might belong to, how confident triage is in it, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub family: Family,
+    pub confidence: f64,
+    pub evidence: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    // How the input was encoded, and how sure detection was of it. The rest of the report is about
+    // the decoded bytes; raw input is analysed as it is.
+    pub encoding: Encoding,
+    pub encoding_confidence: f64,
+    pub decoded_len: usize,
+    // Statistics of the decoded bytes.
+    pub stats: ByteStats,
+    // How many of the decoded bytes, from the start, the xor findings and key sizes are based on.
+    pub sampled_len: usize,
+    // Key sizes ranked by normalised Hamming distance, best first.
+    pub key_sizes: Vec<KeySize>,
+    // How many blocks repeat an earlier one, for each block size checked.
+    pub repeated_blocks: Vec<(usize, usize)>,
+    // Every family considered, most likely first.
+    pub findings: Vec<Finding>,
+}
+
+fn english_confidence(dist: f32) -> f64 {
+    (1.6 - dist as f64).clamp(0.0, 1.0)
+}
+
+// Analyses input and ranks the families it could belong to.
+pub fn triage(input: &[u8]) -> Report {
+    let detection = detect_and_decode(input);
+    let bytes = &detection.bytes;
+    let stats = ByteStats::of(bytes);
+    let sample = &bytes[..bytes.len().min(SAMPLE_LEN)];
+    let mut findings = vec![];
+
+    let en = CharFreq::for_english();
+    let plain_dist = en.dist_from_bytes(bytes);
+    let plain_confidence = english_confidence(plain_dist) * stats.printable;
+    // Empty input is f32::MAX from English, which says nothing worth printing.
+    let plain_evidence = if bytes.is_empty() {
+        "nothing to compare with English".to_string()
+    } else {
+        format!("{:.0}% printable, English distance {:.2}", stats.printable * 100.0, plain_dist)
+    };
+    findings.push(Finding {
+        family: Family::Plaintext,
+        confidence: plain_confidence,
+        evidence: plain_evidence,
+    });
+
+    // Key 0 leaves the input as it is, which the plaintext finding already covers. Input that
+    // already reads as English doesn't need xoring to explain it, however well a key does.
+    let explained = 1.0 - plain_confidence;
+    let single = rank_xor_keys(sample, 2).into_iter().find(|c| c.key != 0);
+    findings.push(match single {
+        Some(c) => {
+            Finding {
+                family: Family::SingleByteXor,
+                confidence: english_confidence(c.score) * explained,
+                evidence: format!("key {:#04x} gives English distance {:.2}", c.key, c.score),
+            }
+        }
+        None => {
+            Finding {
+                family: Family::SingleByteXor,
+                confidence: 0.0,
+                evidence: "nothing to try keys on".to_string(),
+            }
+        }
+    });
+
+    let broken = break_repeating_xor(sample);
+    let key_len = broken.key.len();
+    let repeating = if key_len < 2 {
+        Finding {
+            family: Family::RepeatingXor,
+            confidence: 0.0,
+            evidence: "no key longer than a byte fits".to_string(),
+        }
+    } else if sample.len() < key_len * MIN_BYTES_PER_KEY_BYTE {
+        Finding {
+            family: Family::RepeatingXor,
+            confidence: 0.0,
+            evidence: format!("too short to trust the {} byte key found", key_len),
+        }
+    } else {
+        Finding {
+            family: Family::RepeatingXor,
+            confidence: english_confidence(broken.score) * explained,
+            evidence: format!("{} byte key {:?} gives English distance {:.2}",
+                              key_len,
+                              String::from_utf8_lossy(&broken.key),
+                              broken.score),
+        }
+    };
+    findings.push(repeating);
+
+    let repeats: Vec<(usize, usize)> =
+        BLOCK_SIZES.iter().map(|&size| (size, repeated_blocks(bytes, size))).collect();
+    let ecb = infer_block_size(bytes).map(|size| (size, repeated_blocks(bytes, size)));
+    findings.push(match ecb {
+        None => {
+            Finding {
+                family: Family::Ecb(16),
+                confidence: 0.0,
+                evidence: "no repeated 8, 16 or 32 byte blocks".to_string(),
+            }
+        }
+        Some((block_size, count)) => {
+            // Blocks of random bytes essentially never repeat, so each repeat is strong evidence,
+            // unless the input reads as text, which repeats itself all the time.
+            Finding {
+                family: Family::Ecb(block_size),
+                confidence: (1.0 - 0.5f64.powi(count as i32 + 1)) * explained,
+                evidence: format!("{} repeated {} byte blocks in {}",
+                                  count,
+                                  block_size,
+                                  bytes.len()),
+            }
+        }
+    });
+
+    // Entropy can't exceed log2 of the length, so short inputs are judged against that.
+    let max_entropy = (bytes.len() as f64).log2().min(8.0);
+    let random = if bytes.len() < 64 {
+        0.0
+    } else {
+        let evenness = ((stats.entropy / max_entropy - 0.85) / 0.1).clamp(0.0, 1.0);
+        if stats.uniformity.p_value > 0.001 {
+            evenness
+        } else {
+            evenness / 2.0
+        }
+    };
+    findings.push(Finding {
+        family: Family::Random,
+        confidence: random,
+        evidence: format!("entropy {:.2} bits per byte, uniformity p-value {:.3}",
+                          stats.entropy,
+                          stats.uniformity.p_value),
+    });
+
+    findings.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Report {
+        encoding: detection.encoding,
+        encoding_confidence: detection.confidence,
+        decoded_len: bytes.len(),
+        key_sizes: rank_key_sizes(sample, 2..41, KeySizeMethod::Hamming),
+        stats,
+        sampled_len: sample.len(),
+        repeated_blocks: repeats,
+        findings,
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f,
+                 "input: {:?} (confidence {:.2}), {} bytes",
+                 self.encoding,
+                 self.encoding_confidence,
+                 self.decoded_len)?;
+        let s = &self.stats;
+        writeln!(f,
+                 "bytes: {} distinct, {:.0}% printable, entropy {:.2} bits per byte, index of \
+                  coincidence {:.4}",
+                 s.distinct,
+                 s.printable * 100.0,
+                 s.entropy,
+                 s.index_of_coincidence)?;
+        write!(f,
+               "uniformity: chi-squared {:.1}, p-value {:.3}, serial correlation ",
+               s.uniformity.chi_squared,
+               s.uniformity.p_value)?;
+        match s.serial_correlation {
+            Some(c) => writeln!(f, "{:.3}", c)?,
+            None => writeln!(f, "undefined")?,
+        }
+        let sizes: Vec<String> = self.key_sizes
+                                     .iter()
+                                     .take(5)
+                                     .map(|k| format!("{} ({:.2})", k.size, k.score))
+                                     .collect();
+        if sizes.is_empty() {
+            writeln!(f, "likely key sizes by Hamming distance: none")?;
+        } else {
+            writeln!(f, "likely key sizes by Hamming distance: {}", sizes.join(", "))?;
+        }
+        if self.sampled_len < self.decoded_len {
+            writeln!(f, "xor and key sizes from the first {} bytes only", self.sampled_len)?;
+        }
+        let repeats: Vec<String> = self.repeated_blocks
+                                       .iter()
+                                       .map(|&(size, count)| format!("{} of {} bytes", count, size))
+                                       .collect();
+        writeln!(f, "repeated blocks: {}", repeats.join(", "))?;
+        writeln!(f)?;
+        for (i, finding) in self.findings.iter().enumerate() {
+            writeln!(f,
+                     "{:2}. {:<28} {:.2}  {}",
+                     i + 1,
+                     finding.family.to_string(),
+                     finding.confidence,
+                     finding.evidence)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use conversions::{hex_to_base64, string_to_hex};
+    use measure::pseudo_random;
+
+    fn top(input: &[u8]) -> Family {
+        triage(input).findings[0].family
+    }
+
+    #[test]
+    fn test_triage_challenges() {
+        let report = triage(include_bytes!("challenges/data/6.txt"));
+        assert_eq!(report.findings[0].family, Family::RepeatingXor);
+        assert_eq!(report.findings.len(), 5);
+        assert!(report.findings.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+        let text = report.to_string();
+        assert!(text.starts_with("input: Base64 (confidence 1.00), 2876 bytes\n"), "{}", text);
+        assert!(text.contains("29 byte key \"Terminator X: Bring the noise\""), "{}", text);
+
+        let c03 = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
+        assert_eq!(top(&string_to_hex(c03)), Family::SingleByteXor);
+        let ecb = include_str!("challenges/data/8.txt").lines().nth(132).unwrap();
+        let report = triage(ecb.as_bytes());
+        assert_eq!(report.encoding, Encoding::Hex);
+        assert_eq!(report.findings[0].family, Family::Ecb(16));
+    }
+
+    #[test]
+    fn test_triage_plaintext_and_noise() {
+        let text = &include_bytes!("data/english.txt")[..1024];
+        assert_eq!(top(text), Family::Plaintext);
+        assert_eq!(top(&pseudo_random(512, 0)), Family::Random);
+        let report = triage(hex_to_base64(&pseudo_random(512, 0)).as_bytes());
+        assert_eq!(report.encoding, Encoding::Base64);
+        assert_eq!(report.decoded_len, 512);
+        assert_eq!(report.sampled_len, 512);
+        assert!(!report.to_string().contains("first"));
+        assert_eq!(report.findings[0].family, Family::Random);
+    }
+
+    #[test]
+    fn test_report_of_nothing() {
+        // Empty input still gets a report.
+        let report = triage(b"");
+        assert!(report.findings.iter().all(|f| f.confidence == 0.0));
+        let text = report.to_string();
+        assert!(text.contains("0.00  nothing to compare with English\n"), "{}", text);
+        assert!(text.contains("likely key sizes by Hamming distance: none\n"), "{}", text);
+        assert!(text.contains("entropy 0.00 bits per byte"), "{}", text);
+        assert!(!text.contains("-0.00"), "{}", text);
+    }
+}